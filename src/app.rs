@@ -1,6 +1,11 @@
-use crate::data::AppData;
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use uuid::Uuid;
 
-pub const GRID_COLUMNS: usize = 3;
+use crate::clock::Clock;
+use crate::data::{format_amount, AppData, Habit, HabitKind, Schedule};
+use crate::history::{Day, History, Operation};
+use crate::storage::Change;
+use crate::theme::Theme;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum InputMode {
@@ -9,6 +14,21 @@ pub enum InputMode {
     Adding,
     Renaming,
     Deleting,
+    Scheduling,
+    SettingTarget,
+    EnteringAmount,
+    /// Writing the note for the entry date
+    EditingNote,
+    /// Moving a date cursor across the selected card's heatmap
+    Cursor,
+    /// Full-year heatmap and stats of the selected habit
+    Detail,
+    /// Month calendar of the selected habit, or of all habits together
+    Calendar,
+    /// List of archived habits, to restore or delete for good
+    Archive,
+    /// Popup listing every key of the main screen
+    Help,
 }
 
 pub struct App {
@@ -17,40 +37,84 @@ pub struct App {
     pub selected_index: usize,
     pub input_mode: InputMode,
     pub input_buffer: String,
+    pub input_error: Option<String>,
     pub scroll_offset: usize, // Now represents row offset, not card offset
     pub show_stats: bool,
+    /// Day under the heatmap cursor; set while in cursor mode, including popups opened from it
+    pub cursor_date: Option<NaiveDate>,
+    /// Screen the cursor belongs to (`Cursor` or `Calendar`), returned to when a popup closes
+    pub cursor_mode: InputMode,
+    /// Calendar shows all habits together instead of the selected one
+    pub calendar_all: bool,
+    /// Edits not yet written to storage
+    pub changes: Vec<Change>,
+    /// Another instance has the data file open, so edits are refused
+    pub read_only: bool,
+    /// Name of the profile whose habits are open, if not the default set
+    pub profile: Option<String>,
+    /// One-off message shown in the footer until the next key press
+    pub status: Option<String>,
+    /// The date everything on screen is relative to, advanced by `tick`
+    pub today: NaiveDate,
+    pub clock: Box<dyn Clock>,
+    /// Year shown in the detail view
+    pub detail_year: i32,
+    /// Index of the note selected in the detail view, oldest first
+    pub detail_note: Option<usize>,
+    /// Position of the selected habit in the archive screen
+    pub archive_index: usize,
+    /// Habit cards per row, fitted to the terminal width before each draw
+    pub columns: usize,
+    /// Upper limit on `columns` from the config
+    pub max_columns: Option<usize>,
+    pub theme: Theme,
+    /// Edits made this session, for undo and redo
+    pub history: History,
 }
 
 impl App {
-    pub fn new(data: AppData) -> Self {
+    pub fn new(data: AppData, clock: Box<dyn Clock>) -> Self {
         Self {
             data,
             should_quit: false,
             selected_index: 0,
             input_mode: InputMode::Normal,
             input_buffer: String::new(),
+            input_error: None,
             scroll_offset: 0,
             show_stats: false,
+            cursor_date: None,
+            cursor_mode: InputMode::Cursor,
+            calendar_all: false,
+            changes: Vec::new(),
+            read_only: false,
+            profile: None,
+            status: None,
+            today: clock.today(),
+            detail_year: 0,
+            detail_note: None,
+            archive_index: 0,
+            clock,
+            columns: 1,
+            max_columns: None,
+            theme: Theme::default(),
+            history: History::default(),
         }
     }
 
     /// Get the (row, col) position for a given linear index
     pub fn grid_position(&self, index: usize) -> (usize, usize) {
-        (index / GRID_COLUMNS, index % GRID_COLUMNS)
+        (index / self.columns, index % self.columns)
     }
 
     /// Get the current selection's row
     pub fn selected_row(&self) -> usize {
-        self.selected_index / GRID_COLUMNS
+        self.selected_position() / self.columns
     }
 
     /// Get the total number of rows in the grid
     pub fn total_rows(&self) -> usize {
-        if self.data.habits.is_empty() {
-            0
-        } else {
-            (self.data.habits.len() + GRID_COLUMNS - 1) / GRID_COLUMNS
-        }
+        self.grid_habits().len().div_ceil(self.columns)
     }
 
     pub fn adjust_scroll(&mut self, visible_height: u16, card_height: u16) {
@@ -60,6 +124,10 @@ impl App {
         let visible_rows = (visible_height / card_height).max(1) as usize;
         let selected_row = self.selected_row();
 
+        // Don't leave empty rows below the last one, e.g. after the terminal got wider
+        let max_offset = self.total_rows().saturating_sub(visible_rows);
+        self.scroll_offset = self.scroll_offset.min(max_offset);
+
         // If selection is above visible area, scroll up
         if selected_row < self.scroll_offset {
             self.scroll_offset = selected_row;
@@ -71,124 +139,745 @@ impl App {
         }
     }
 
+    /// Pick up a change of date while the app is open, e.g. at midnight.
+    /// Returns true if the day rolled over and the screen needs redrawing.
+    pub fn tick(&mut self) -> bool {
+        let today = self.clock.today();
+        if today == self.today {
+            return false;
+        }
+        self.today = today;
+        true
+    }
+
     pub fn quit(&mut self) {
         self.should_quit = true;
     }
 
+    pub fn show_help(&mut self) {
+        self.input_mode = InputMode::Help;
+    }
+
     pub fn toggle_stats(&mut self) {
         self.show_stats = !self.show_stats;
     }
 
     /// Move down one row (j key)
     pub fn select_down(&mut self) {
-        if self.data.habits.is_empty() {
-            return;
-        }
-        let new_index = self.selected_index + GRID_COLUMNS;
-        if new_index < self.data.habits.len() {
-            self.selected_index = new_index;
-        }
+        self.select_position(self.selected_position() + self.columns);
     }
 
     /// Move up one row (k key)
     pub fn select_up(&mut self) {
-        if self.selected_index >= GRID_COLUMNS {
-            self.selected_index -= GRID_COLUMNS;
+        let position = self.selected_position();
+        if position >= self.columns {
+            self.select_position(position - self.columns);
         }
     }
 
     /// Move left one column (h key)
     pub fn select_left(&mut self) {
-        let (_, col) = self.grid_position(self.selected_index);
+        let position = self.selected_position();
+        let (_, col) = self.grid_position(position);
         if col > 0 {
-            self.selected_index -= 1;
+            self.select_position(position - 1);
         }
     }
 
     /// Move right one column (l key)
     pub fn select_right(&mut self) {
-        if self.data.habits.is_empty() {
-            return;
-        }
-        let (_, col) = self.grid_position(self.selected_index);
-        if col < GRID_COLUMNS - 1 && self.selected_index + 1 < self.data.habits.len() {
-            self.selected_index += 1;
+        let position = self.selected_position();
+        let (_, col) = self.grid_position(position);
+        if col < self.columns - 1 {
+            self.select_position(position + 1);
         }
     }
 
     pub fn select_first(&mut self) {
-        self.selected_index = 0;
+        self.select_position(0);
     }
 
     pub fn select_last(&mut self) {
-        if !self.data.habits.is_empty() {
-            self.selected_index = self.data.habits.len() - 1;
+        self.select_position(self.grid_habits().len().saturating_sub(1));
+    }
+
+    /// The habit selected in the grid; never an archived one
+    pub fn selected_habit(&self) -> Option<&Habit> {
+        self.data.habits.get(self.selected_index).filter(|h| !h.is_archived())
+    }
+
+    fn selected_habit_mut(&mut self) -> Option<&mut Habit> {
+        self.data.habits.get_mut(self.selected_index).filter(|h| !h.is_archived())
+    }
+
+    /// Indices into `data.habits` of the habits shown in the grid, in grid order
+    pub fn grid_habits(&self) -> Vec<usize> {
+        (0..self.data.habits.len())
+            .filter(|&i| !self.data.habits[i].is_archived())
+            .collect()
+    }
+
+    /// Indices into `data.habits` of archived habits, in the order the archive screen lists them
+    pub fn archived_habits(&self) -> Vec<usize> {
+        (0..self.data.habits.len())
+            .filter(|&i| self.data.habits[i].is_archived())
+            .collect()
+    }
+
+    /// Position of the selected habit among the grid's habits
+    fn selected_position(&self) -> usize {
+        self.grid_habits()
+            .iter()
+            .position(|&i| i == self.selected_index)
+            .unwrap_or(0)
+    }
+
+    /// Select the habit at `position` in the grid, if there is one
+    fn select_position(&mut self, position: usize) {
+        if let Some(&index) = self.grid_habits().get(position) {
+            self.selected_index = index;
+        }
+    }
+
+    /// Keep the selection on a habit in the grid, moving to the next one (or else the
+    /// previous one) if the selected habit was archived or removed
+    fn fix_selection(&mut self) {
+        if self.selected_habit().is_some() {
+            return;
+        }
+        let grid = self.grid_habits();
+        if let Some(&index) = grid.iter().find(|&&i| i >= self.selected_index).or(grid.last()) {
+            self.selected_index = index;
+        }
+    }
+
+    /// Take the edits made since the last call, to be written to storage
+    pub fn take_changes(&mut self) -> Vec<Change> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Whether edits are allowed, leaving a footer message if they aren't
+    fn check_writable(&mut self) -> bool {
+        if self.read_only {
+            self.status = Some("Read-only: another heat instance has this data open".to_string());
+        }
+        !self.read_only
+    }
+
+    /// Swap in data reloaded from storage, keeping the same habit selected if it still exists
+    pub fn replace_data(&mut self, data: AppData) {
+        let selected_id = self.selected_habit().map(|h| h.id);
+        self.data = data;
+
+        if let Some(index) = selected_id.and_then(|id| self.data.habits.iter().position(|h| h.id == id)) {
+            self.selected_index = index;
+        }
+        self.fix_selection();
+        self.archive_index = self.archive_index.min(self.archived_habits().len().saturating_sub(1));
+
+        // Popups and the cursor refer to the selected habit, so drop them if it's gone.
+        // The archive screen and its delete popup don't.
+        let in_archive = matches!(self.input_mode, InputMode::Archive | InputMode::Deleting);
+        if self.selected_habit().map(|h| h.id) != selected_id && !in_archive {
+            self.cursor_date = None;
+            self.input_mode = InputMode::Normal;
+            self.input_buffer.clear();
+            self.input_error = None;
+        }
+    }
+
+    /// Remember an edit just made to the selected habit's `date` for undo, given the day before it
+    fn record_day(&mut self, description: String, date: NaiveDate, before: Day) {
+        if let Some(habit) = self.selected_habit() {
+            let operation = Operation::Day {
+                habit: habit.id,
+                date,
+                before,
+                after: Day::of(habit, date),
+            };
+            self.history.record(description, operation);
+        }
+    }
+
+    /// Note that the selected habit changed as a whole
+    fn habit_changed(&mut self) {
+        if let Some(habit) = self.selected_habit() {
+            self.changes.push(Change::Habit(habit.id));
         }
     }
 
     pub fn toggle_today(&mut self) {
-        if let Some(habit) = self.data.habits.get_mut(self.selected_index) {
-            habit.toggle_today();
+        if !self.check_writable() {
+            return;
         }
+        self.toggle_date(self.today);
+    }
+
+    fn toggle_date(&mut self, date: NaiveDate) {
+        let Some(habit) = self.selected_habit_mut() else {
+            return;
+        };
+        let before = Day::of(habit, date);
+        habit.toggle_date(date);
+        let change = Change::for_day(habit, date);
+        let description = format!("toggle {} on {}", habit.name, date);
+        self.changes.push(change);
+        self.record_day(description, date, before);
+    }
+
+    /// Day that toggles and amount entries apply to: the cursor if active, otherwise today
+    pub fn entry_date(&self) -> NaiveDate {
+        self.cursor_date.unwrap_or(self.today)
+    }
+
+    pub fn start_cursor(&mut self) {
+        if self.selected_habit().is_some() {
+            self.cursor_date = Some(self.today);
+            self.cursor_mode = InputMode::Cursor;
+            self.input_mode = InputMode::Cursor;
+        }
+    }
+
+    pub fn exit_cursor(&mut self) {
+        self.cursor_date = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Move the cursor by `days`, staying between the habit's creation date and today
+    pub fn move_cursor(&mut self, days: i64) {
+        let (Some(cursor), Some(habit)) = (self.cursor_date, self.selected_habit()) else {
+            return;
+        };
+        let earliest = habit.created_at.min(self.today);
+        let target = (cursor + Duration::days(days)).clamp(earliest, self.today);
+        self.cursor_date = Some(target);
+    }
+
+    /// Open the full-year view of the selected habit at the current year
+    pub fn start_detail(&mut self) {
+        if self.selected_habit().is_some() {
+            self.detail_year = self.today.year();
+            self.detail_note = None;
+            self.input_mode = InputMode::Detail;
+        }
+    }
+
+    pub fn exit_detail(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Page the detail view by `years`, staying between the habit's first tracked year and this year
+    pub fn page_detail(&mut self, years: i32) {
+        let Some(habit) = self.selected_habit() else {
+            return;
+        };
+        let first_year = habit.first_tracked_day().year().min(self.today.year());
+        self.detail_year = (self.detail_year + years).clamp(first_year, self.today.year());
+        self.detail_note = None;
+    }
+
+    /// Open the month calendar with today selected
+    pub fn start_calendar(&mut self) {
+        if self.selected_habit().is_some() {
+            self.cursor_date = Some(self.today);
+            self.cursor_mode = InputMode::Calendar;
+            self.input_mode = InputMode::Calendar;
+        }
+    }
+
+    /// Whether the calendar is open, possibly under a popup
+    pub fn in_calendar(&self) -> bool {
+        self.cursor_date.is_some() && self.cursor_mode == InputMode::Calendar
+    }
+
+    pub fn toggle_calendar_all(&mut self) {
+        self.calendar_all = !self.calendar_all;
+    }
+
+    /// Earliest day the calendar goes back to: the first tracked day of the habits it shows
+    fn calendar_start(&self) -> NaiveDate {
+        let first = if self.calendar_all {
+            self.data
+                .habits
+                .iter()
+                .filter(|h| !h.is_archived())
+                .map(Habit::first_tracked_day)
+                .min()
+        } else {
+            self.selected_habit().map(Habit::first_tracked_day)
+        };
+        first.unwrap_or(self.today).min(self.today)
+    }
+
+    /// Move the calendar's selected day by `days`, or by whole `months`, staying in the tracked range
+    pub fn move_calendar(&mut self, days: i64, months: i32) {
+        let Some(cursor) = self.cursor_date else {
+            return;
+        };
+        let moved = if months < 0 {
+            cursor.checked_sub_months(Months::new(months.unsigned_abs()))
+        } else {
+            cursor.checked_add_months(Months::new(months as u32))
+        };
+        let target = moved.unwrap_or(cursor) + Duration::days(days);
+        self.cursor_date = Some(target.clamp(self.calendar_start(), self.today));
+    }
+
+    /// Toggle the calendar's selected day, or log an amount for a measured habit
+    pub fn confirm_calendar_day(&mut self) {
+        if self.calendar_all {
+            self.status = Some("Press Tab to pick a single habit before toggling days".to_string());
+        } else if self.selected_habit().is_some_and(|h| h.is_measured()) {
+            self.start_entering_amount();
+        } else {
+            self.toggle_at_cursor();
+        }
+    }
+
+    pub fn toggle_at_cursor(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        if let Some(date) = self.cursor_date {
+            self.toggle_date(date);
+        }
+    }
+
+    /// Skip the entry date for the selected habit, or take the skip back
+    pub fn toggle_skip(&mut self) {
+        if self.calendar_all && self.in_calendar() {
+            self.status = Some("Press Tab to pick a single habit before skipping days".to_string());
+            return;
+        }
+        if !self.check_writable() {
+            return;
+        }
+        let date = self.entry_date();
+        let Some(habit) = self.selected_habit_mut() else {
+            return;
+        };
+        let before = Day::of(habit, date);
+        let skipped = habit.toggle_skip(date);
+        let verb = if skipped { "skip" } else { "unskip" };
+        let description = format!("{} {} on {}", verb, habit.name, date);
+        self.status = Some(format!(
+            "{} {} for {}",
+            if skipped { "Skipped" } else { "Unskipped" },
+            date,
+            habit.name
+        ));
+        self.habit_changed();
+        self.record_day(description, date, before);
     }
 
     pub fn start_adding(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
         self.input_mode = InputMode::Adding;
         self.input_buffer.clear();
     }
 
     pub fn cancel_input(&mut self) {
-        self.input_mode = InputMode::Normal;
+        self.input_mode = if self.cursor_date.is_some() {
+            self.cursor_mode
+        } else {
+            InputMode::Normal
+        };
         self.input_buffer.clear();
+        self.input_error = None;
     }
 
-    pub fn confirm_add(&mut self) {
+    /// Apply the text typed into the open popup, however the popup's mode applies it
+    pub fn confirm_input(&mut self) {
+        match self.input_mode {
+            InputMode::Adding => self.confirm_add(),
+            InputMode::Renaming => self.confirm_rename(),
+            InputMode::Scheduling => self.confirm_schedule(),
+            InputMode::SettingTarget => self.confirm_target(),
+            InputMode::EnteringAmount => self.confirm_amount(),
+            InputMode::EditingNote => self.confirm_note(),
+            _ => {}
+        }
+    }
+
+    fn confirm_add(&mut self) {
         let name = self.input_buffer.trim().to_string();
         if !name.is_empty() {
-            let habit = crate::data::Habit::new(name);
+            let habit = Habit::new(name, self.clock.as_ref());
+            let description = format!("add {}", habit.name);
+            let operation = Operation::Presence {
+                habit: habit.clone(),
+                position: self.data.habits.len(),
+                added: true,
+            };
+            self.changes.push(Change::Habit(habit.id));
             self.data.habits.push(habit);
             self.selected_index = self.data.habits.len() - 1;
+            self.history.record(description, operation);
         }
         self.input_mode = InputMode::Normal;
         self.input_buffer.clear();
     }
 
     pub fn start_renaming(&mut self) {
-        if let Some(habit) = self.data.habits.get(self.selected_index) {
+        if !self.check_writable() {
+            return;
+        }
+        if let Some(habit) = self.selected_habit() {
             self.input_buffer = habit.name.clone();
             self.input_mode = InputMode::Renaming;
         }
     }
 
-    pub fn confirm_rename(&mut self) {
+    fn confirm_rename(&mut self) {
         let name = self.input_buffer.trim().to_string();
-        if !name.is_empty() {
-            if let Some(habit) = self.data.habits.get_mut(self.selected_index) {
-                habit.name = name;
-            }
+        if !name.is_empty()
+            && let Some(habit) = self.selected_habit_mut()
+            && habit.name != name
+        {
+            let description = format!("rename {} to {}", habit.name, name);
+            let operation = Operation::Rename {
+                habit: habit.id,
+                before: std::mem::replace(&mut habit.name, name.clone()),
+                after: name,
+            };
+            self.habit_changed();
+            self.history.record(description, operation);
         }
         self.input_mode = InputMode::Normal;
         self.input_buffer.clear();
     }
 
+    pub fn start_scheduling(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        if let Some(habit) = self.selected_habit() {
+            self.input_buffer = habit.schedule.to_string();
+            self.input_error = None;
+            self.input_mode = InputMode::Scheduling;
+        }
+    }
+
+    /// Apply the typed schedule, keeping the popup open with an error if it doesn't parse
+    fn confirm_schedule(&mut self) {
+        match self.input_buffer.parse::<Schedule>() {
+            Ok(schedule) => {
+                if let Some(habit) = self.selected_habit_mut() {
+                    let description = format!("schedule {} as {}", habit.name, schedule);
+                    let operation = Operation::Schedule {
+                        habit: habit.id,
+                        before: std::mem::replace(&mut habit.schedule, schedule.clone()),
+                        after: schedule,
+                    };
+                    self.habit_changed();
+                    self.history.record(description, operation);
+                }
+                self.cancel_input();
+            }
+            Err(e) => self.input_error = Some(e),
+        }
+    }
+
+    pub fn start_setting_target(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        if let Some(habit) = self.selected_habit() {
+            self.input_buffer = habit.kind.to_string();
+            self.input_error = None;
+            self.input_mode = InputMode::SettingTarget;
+        }
+    }
+
+    /// Apply the typed target, turning the habit into a measured one (or back to a check if
+    /// empty), or into a habit to quit
+    fn confirm_target(&mut self) {
+        let kind = HabitKind::parse_target(&self.input_buffer);
+        let Some(habit) = self.selected_habit_mut() else {
+            return self.cancel_input();
+        };
+        let description = format!("change the target of {}", habit.name);
+        let before = habit.kind.clone();
+        match kind.and_then(|kind| habit.set_kind(kind)) {
+            Ok(()) => {
+                let operation = Operation::Kind {
+                    habit: habit.id,
+                    before,
+                    after: habit.kind.clone(),
+                };
+                self.habit_changed();
+                self.history.record(description, operation);
+                self.cancel_input();
+            }
+            Err(e) => self.input_error = Some(e),
+        }
+    }
+
+    pub fn start_entering_amount(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        if let Some(habit) = self.selected_habit() {
+            let amount = habit.amount_on(self.entry_date());
+            self.input_buffer = if amount > 0.0 {
+                format_amount(amount)
+            } else {
+                String::new()
+            };
+            self.input_error = None;
+            self.input_mode = InputMode::EnteringAmount;
+        }
+    }
+
+    /// Record the amount for the entry date. A leading "+" adds to the amount already logged.
+    fn confirm_amount(&mut self) {
+        let input = self.input_buffer.trim();
+        let (additive, number) = match input.strip_prefix('+') {
+            Some(rest) => (true, rest.trim()),
+            None => (false, input),
+        };
+        let amount = if number.is_empty() {
+            Ok(0.0)
+        } else {
+            number
+                .parse::<f64>()
+                .ok()
+                .filter(|a| a.is_finite() && *a >= 0.0)
+                .ok_or_else(|| format!("invalid amount \"{}\"", number))
+        };
+
+        match amount {
+            Ok(amount) => {
+                let date = self.entry_date();
+                if let Some(habit) = self.selected_habit_mut() {
+                    let before = Day::of(habit, date);
+                    let amount = if additive {
+                        habit.amount_on(date) + amount
+                    } else {
+                        amount
+                    };
+                    habit.set_amount(date, amount);
+                    let description = format!("log {} for {} on {}", format_amount(amount), habit.name, date);
+                    self.habit_changed();
+                    self.record_day(description, date, before);
+                }
+                self.cancel_input();
+            }
+            Err(e) => self.input_error = Some(e),
+        }
+    }
+
+    /// Open the note popup for the entry date, with the day's note to edit if it has one
+    pub fn start_editing_note(&mut self) {
+        if self.calendar_all && self.in_calendar() {
+            self.status = Some("Press Tab to pick a single habit before writing notes".to_string());
+            return;
+        }
+        if !self.check_writable() {
+            return;
+        }
+        if let Some(habit) = self.selected_habit() {
+            self.input_buffer = habit.note_on(self.entry_date()).unwrap_or_default().to_string();
+            self.input_error = None;
+            self.input_mode = InputMode::EditingNote;
+        }
+    }
+
+    fn confirm_note(&mut self) {
+        let date = self.entry_date();
+        let note = std::mem::take(&mut self.input_buffer);
+        if let Some(habit) = self.selected_habit_mut()
+            && habit.note_on(date).unwrap_or_default() != note.trim()
+        {
+            let before = Day::of(habit, date);
+            habit.set_note(date, &note);
+            let description = format!("edit the note for {} on {}", habit.name, date);
+            self.habit_changed();
+            self.record_day(description, date, before);
+        }
+        self.cancel_input();
+    }
+
+    /// Select the next (or with a negative `step`, previous) note in the detail view,
+    /// showing the year it was written in
+    pub fn move_detail_note(&mut self, step: i64) {
+        let Some(habit) = self.selected_habit() else {
+            return;
+        };
+        let count = habit.notes.len();
+        if count == 0 {
+            return;
+        }
+        let index = match self.detail_note {
+            Some(index) => (index as i64 + step).clamp(0, count as i64 - 1) as usize,
+            None if step > 0 => 0,
+            None => count - 1,
+        };
+        if let Some(date) = habit.notes.keys().nth(index) {
+            self.detail_year = date.year();
+        }
+        self.detail_note = Some(index);
+    }
+
+    /// Hide the selected habit from the grid, keeping its history
+    pub fn archive_selected(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        let today = self.today;
+        let Some(habit) = self.selected_habit_mut() else {
+            return;
+        };
+        let operation = Operation::Archive {
+            habit: habit.id,
+            before: habit.archived_on.replace(today),
+            after: Some(today),
+        };
+        let status = format!("Archived {}; press A to see archived habits", habit.name);
+        let description = format!("archive {}", habit.name);
+        let change = Change::Habit(habit.id);
+        self.status = Some(status);
+        self.changes.push(change);
+        self.fix_selection();
+        self.history.record(description, operation);
+    }
+
+    pub fn start_archive(&mut self) {
+        self.archive_index = 0;
+        self.input_mode = InputMode::Archive;
+    }
+
+    pub fn exit_archive(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// The habit selected in the archive screen
+    pub fn selected_archived(&self) -> Option<&Habit> {
+        let index = *self.archived_habits().get(self.archive_index)?;
+        self.data.habits.get(index)
+    }
+
+    pub fn move_archive(&mut self, step: i64) {
+        let count = self.archived_habits().len();
+        if count > 0 {
+            self.archive_index = (self.archive_index as i64 + step).clamp(0, count as i64 - 1) as usize;
+        }
+    }
+
+    /// Put the selected archived habit back in the grid, in its old place, and select it
+    pub fn restore_archived(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        let Some(&index) = self.archived_habits().get(self.archive_index) else {
+            return;
+        };
+        let habit = &mut self.data.habits[index];
+        let operation = Operation::Archive {
+            habit: habit.id,
+            before: habit.archived_on.take(),
+            after: None,
+        };
+        let description = format!("restore {}", habit.name);
+        self.status = Some(format!("Restored {}", habit.name));
+        self.changes.push(Change::Habit(habit.id));
+        self.selected_index = index;
+        self.move_archive(0);
+        self.history.record(description, operation);
+    }
+
+    /// Ask before deleting the selected archived habit for good
     pub fn start_deleting(&mut self) {
-        if !self.data.habits.is_empty() {
+        if !self.check_writable() {
+            return;
+        }
+        if self.selected_archived().is_some() {
             self.input_mode = InputMode::Deleting;
         }
     }
 
+    /// Delete the selected archived habit and all of its history
     pub fn confirm_delete(&mut self) {
-        if self.selected_index < self.data.habits.len() {
-            self.data.habits.remove(self.selected_index);
-            // Adjust selected_index if we deleted the last item
-            if self.selected_index >= self.data.habits.len() && !self.data.habits.is_empty() {
-                self.selected_index = self.data.habits.len() - 1;
+        if let Some(&index) = self.archived_habits().get(self.archive_index) {
+            let habit = self.data.habits.remove(index);
+            self.changes.push(Change::Removed(habit.id));
+            if self.selected_index > index {
+                self.selected_index -= 1;
             }
+            self.fix_selection();
+            self.move_archive(0);
+            let description = format!("delete {}", habit.name);
+            let operation = Operation::Presence {
+                habit,
+                position: index,
+                added: false,
+            };
+            self.history.record(description, operation);
         }
-        self.input_mode = InputMode::Normal;
+        self.input_mode = InputMode::Archive;
     }
 
     pub fn cancel_delete(&mut self) {
-        self.input_mode = InputMode::Normal;
+        self.input_mode = InputMode::Archive;
+    }
+
+    /// Swap the selected habit with the next (or with a negative `step`, previous) one in the grid
+    pub fn move_selected(&mut self, step: i64) {
+        if !self.check_writable() {
+            return;
+        }
+        let Some(habit) = self.selected_habit() else {
+            return;
+        };
+        let (id, description) = (habit.id, format!("move {}", habit.name));
+        if let Some(index) = self.data.move_habit(id, step) {
+            self.selected_index = index;
+            self.changes.push(Change::Order);
+            self.history.record(description, Operation::Move { habit: id, step });
+        }
+    }
+
+    /// Revert the last edit made this session
+    pub fn undo(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        self.status = Some(match self.history.undo(&mut self.data) {
+            Some((description, Ok((habit, changes)))) => {
+                self.after_history(habit, changes);
+                format!("Undid: {}", description)
+            }
+            Some((description, Err(e))) => format!("Can't undo {}: {}", description, e),
+            None => "Nothing to undo".to_string(),
+        });
+    }
+
+    /// Make the last undone edit again
+    pub fn redo(&mut self) {
+        if !self.check_writable() {
+            return;
+        }
+        self.status = Some(match self.history.redo(&mut self.data) {
+            Some((description, Ok((habit, changes)))) => {
+                self.after_history(habit, changes);
+                format!("Redid: {}", description)
+            }
+            Some((description, Err(e))) => format!("Can't redo {}: {}", description, e),
+            None => "Nothing to redo".to_string(),
+        });
+    }
+
+    /// Store the changes an undo or redo made, and select the habit it touched if it's in the grid
+    fn after_history(&mut self, habit: Uuid, changes: Vec<Change>) {
+        self.changes.extend(changes);
+        if let Some(index) = self.data.habits.iter().position(|h| h.id == habit)
+            && !self.data.habits[index].is_archived()
+        {
+            self.selected_index = index;
+        }
+        self.fix_selection();
+        self.archive_index = self.archive_index.min(self.archived_habits().len().saturating_sub(1));
     }
 }
@@ -0,0 +1,563 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use chrono::{Duration, NaiveDate, Weekday};
+use clap::{Parser, Subcommand};
+
+use crate::clock::Clock;
+use crate::data::{AppData, Habit, HabitKind, Schedule, Vacation};
+use crate::report::Report;
+use crate::storage::{self, Backend, Change, Storage};
+
+#[derive(Debug, Parser)]
+#[command(name = "heat", version, about = "Track habits on a heatmap")]
+pub struct Cli {
+    /// Config file to read instead of the default one
+    #[arg(long, global = true, env = "HEAT_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Data file to use instead of the default one
+    #[arg(long, global = true, env = "HEAT_DATA", conflicts_with = "profile")]
+    pub data: Option<PathBuf>,
+
+    /// Named set of habits with its own data file, e.g. "work"
+    #[arg(long, global = true, env = "HEAT_PROFILE", value_parser = parse_profile)]
+    pub profile: Option<String>,
+
+    /// Storage backend for habit data [default: json]
+    #[arg(long, global = true, value_enum, env = "HEAT_STORAGE")]
+    pub storage: Option<Backend>,
+
+    /// Hour (0-23) at which a new day starts, so late nights count towards the day before
+    #[arg(
+        long,
+        global = true,
+        env = "HEAT_DAY_START",
+        value_parser = clap::value_parser!(u32).range(0..24)
+    )]
+    pub day_start: Option<u32>,
+
+    /// First day of the week, for weekly goals and the heatmap, e.g. "mon" [default: sun]
+    #[arg(long, global = true, env = "HEAT_WEEK_START", value_parser = parse_weekday)]
+    pub week_start: Option<Weekday>,
+
+    /// Run a command instead of opening the TUI
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Add a new habit
+    Add {
+        name: String,
+        /// How often the habit is due, e.g. "daily", "mon,wed,fri", "3x/week", "every 2 days"
+        #[arg(long, value_parser = parse_schedule)]
+        schedule: Option<Schedule>,
+        /// Daily target for a measured habit, e.g. "8 glasses"
+        #[arg(long)]
+        target: Option<String>,
+        /// A habit to quit, e.g. "no smoking": `heat done` then records a slip
+        #[arg(long, conflicts_with = "target")]
+        quit: bool,
+    },
+    /// Mark a habit as done (log an amount for a measured habit, or a slip for a habit to quit)
+    Done {
+        /// Habit name, name prefix or id
+        habit: String,
+        /// Day to mark: "today", "yesterday" or YYYY-MM-DD
+        #[arg(long, value_parser = parse_date)]
+        date: Option<DateArg>,
+        /// Amount to add for a measured habit; defaults to the full target
+        #[arg(long)]
+        amount: Option<f64>,
+        /// Note for the day, e.g. "ran 5k, knee hurt"
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Clear a habit's completion for a day
+    Undo {
+        /// Habit name, name prefix or id
+        habit: String,
+        /// Day to clear: "today", "yesterday" or YYYY-MM-DD
+        #[arg(long, value_parser = parse_date)]
+        date: Option<DateArg>,
+    },
+    /// Skip a day, so missing it doesn't break the streak; run again to take it back
+    Skip {
+        /// Habit name, name prefix or id
+        habit: String,
+        /// Day to skip: "today", "yesterday" or YYYY-MM-DD
+        #[arg(long, value_parser = parse_date)]
+        date: Option<DateArg>,
+    },
+    /// Add, list or remove vacations: days off that don't break streaks
+    Vacation {
+        #[command(subcommand)]
+        command: VacationCommand,
+    },
+    /// Hide a habit from the grid and listings, keeping its history
+    Archive {
+        /// Habit name, name prefix or id
+        habit: String,
+    },
+    /// Bring back an archived habit
+    Unarchive {
+        /// Habit name, name prefix or id
+        habit: String,
+    },
+    /// Delete a habit and all of its history
+    Rm {
+        /// Habit name, name prefix or id
+        habit: String,
+    },
+    /// List habits with today's status
+    List {
+        /// Print a JSON report instead of a table
+        #[arg(long)]
+        json: bool,
+        /// List archived habits instead
+        #[arg(long)]
+        archived: bool,
+    },
+    /// List backups of the data file, or roll back to one
+    ///
+    /// A backup is taken before the first save of each session, unless the last one is
+    /// less than an hour old; set HEAT_BACKUPS to change how many are kept (default 5,
+    /// 0 disables them).
+    Restore {
+        /// Backup number from the listing (1 is the newest) or a path to a backup file
+        backup: Option<String>,
+    },
+    /// Show streaks and completion rates
+    Stats {
+        /// Only show this habit
+        habit: Option<String>,
+        /// Print a JSON report instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum VacationCommand {
+    /// Take days off from every habit, or from one with --habit
+    Add {
+        /// First day: "today", "yesterday" or YYYY-MM-DD
+        #[arg(value_parser = parse_date)]
+        start: DateArg,
+        /// Last day; defaults to the first
+        #[arg(value_parser = parse_date)]
+        end: Option<DateArg>,
+        /// Only this habit (name, name prefix or id)
+        #[arg(long)]
+        habit: Option<String>,
+    },
+    /// List vacations, numbered for `heat vacation rm`
+    List,
+    /// Remove a vacation by its number from the listing
+    Rm { number: usize },
+}
+
+fn parse_schedule(s: &str) -> Result<Schedule, String> {
+    s.parse()
+}
+
+/// Profile names become file names, so keep them to letters, digits, '-' and '_'
+fn parse_profile(s: &str) -> Result<String, String> {
+    let valid = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(s.to_string())
+    } else {
+        Err(format!(
+            "invalid profile \"{}\", use only letters, digits, '-' and '_'",
+            s
+        ))
+    }
+}
+
+fn parse_weekday(s: &str) -> Result<Weekday, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("invalid weekday \"{}\", expected e.g. \"mon\" or \"sunday\"", s))
+}
+
+/// A `--date` argument. Relative days are resolved once the day start hour is known.
+#[derive(Debug, Clone, Copy)]
+pub enum DateArg {
+    Today,
+    Yesterday,
+    On(NaiveDate),
+}
+
+impl DateArg {
+    fn resolve(self, today: NaiveDate) -> NaiveDate {
+        match self {
+            DateArg::Today => today,
+            DateArg::Yesterday => today - Duration::days(1),
+            DateArg::On(date) => date,
+        }
+    }
+}
+
+/// Parse "today", "yesterday" or an ISO date
+fn parse_date(s: &str) -> Result<DateArg, String> {
+    match s.trim().to_lowercase().as_str() {
+        "today" => Ok(DateArg::Today),
+        "yesterday" => Ok(DateArg::Yesterday),
+        other => NaiveDate::parse_from_str(other, "%Y-%m-%d")
+            .map(DateArg::On)
+            .map_err(|_| format!("invalid date \"{}\", expected YYYY-MM-DD", s)),
+    }
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn find_habit(data: &AppData, query: &str) -> io::Result<usize> {
+    data.find_habit(query).map_err(invalid_input)
+}
+
+/// Run a subcommand against the data file
+pub fn run(command: Command, store: &mut dyn Storage, clock: &dyn Clock) -> io::Result<()> {
+    let mut data = store.load()?;
+    let today = clock.today();
+
+    match command {
+        Command::Add {
+            name,
+            schedule,
+            target,
+            quit,
+        } => {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                return Err(invalid_input("habit name cannot be empty".to_string()));
+            }
+            let mut habit = Habit::new(name, clock);
+            if let Some(schedule) = schedule {
+                habit.schedule = schedule;
+            }
+            let kind = match target {
+                Some(target) => HabitKind::parse_target(&target).map_err(invalid_input)?,
+                None if quit => HabitKind::Quit,
+                None => HabitKind::Check,
+            };
+            habit.set_kind(kind).map_err(invalid_input)?;
+            println!("Added \"{}\" ({})", habit.name, habit.id);
+            let change = Change::Habit(habit.id);
+            data.habits.push(habit);
+            store.apply(&data, &[change])?;
+        }
+        Command::Done {
+            habit,
+            date,
+            amount,
+            note,
+        } => {
+            let date = date.map_or(today, |d| d.resolve(today));
+            if date > today {
+                return Err(invalid_input(format!("{} is in the future", date)));
+            }
+            let index = find_habit(&data, &habit)?;
+            let habit = &mut data.habits[index];
+            if amount.is_some() && !habit.is_measured() {
+                return Err(invalid_input(format!(
+                    "\"{}\" has no target to log an amount against",
+                    habit.name
+                )));
+            }
+            if let Some(amount) = amount
+                && !(amount.is_finite() && amount >= 0.0)
+            {
+                return Err(invalid_input(format!("invalid amount \"{}\"", amount)));
+            }
+            habit.mark_done(date, amount);
+            println!("{}", describe_day(habit, date));
+            let change = match note {
+                Some(note) => {
+                    habit.set_note(date, &note);
+                    Change::Habit(habit.id)
+                }
+                None => Change::for_day(habit, date),
+            };
+            store.apply(&data, &[change])?;
+        }
+        Command::Undo { habit, date } => {
+            let date = date.map_or(today, |d| d.resolve(today));
+            let index = find_habit(&data, &habit)?;
+            let habit = &mut data.habits[index];
+            // A skip lives with the habit itself, not with the day's completion
+            let was_skipped = habit.skipped.contains(&date);
+            habit.clear_date(date);
+            println!("{}", describe_day(habit, date));
+            let change = if was_skipped {
+                Change::Habit(habit.id)
+            } else {
+                Change::for_day(habit, date)
+            };
+            store.apply(&data, &[change])?;
+        }
+        Command::Skip { habit, date } => {
+            let date = date.map_or(today, |d| d.resolve(today));
+            let index = find_habit(&data, &habit)?;
+            let habit = &mut data.habits[index];
+            let skipped = habit.toggle_skip(date);
+            let status = if skipped { "skipped" } else { "no longer skipped" };
+            println!("{} on {}: {}", habit.name, date, status);
+            let change = Change::Habit(habit.id);
+            store.apply(&data, &[change])?;
+        }
+        Command::Vacation {
+            command:
+                VacationCommand::Add {
+                    start,
+                    end,
+                    habit,
+                },
+        } => {
+            let start = start.resolve(today);
+            let end = end.map_or(start, |d| d.resolve(today));
+            if end < start {
+                return Err(invalid_input(format!("{} is before {}", end, start)));
+            }
+            let vacation = Vacation { start, end };
+            let change = match habit {
+                Some(query) => {
+                    let index = find_habit(&data, &query)?;
+                    let habit = &mut data.habits[index];
+                    habit.vacations.push(vacation);
+                    println!("Added vacation {} for {}", vacation, habit.name);
+                    Change::Habit(habit.id)
+                }
+                None => {
+                    data.vacations.push(vacation);
+                    println!("Added vacation {} for all habits", vacation);
+                    Change::Vacations
+                }
+            };
+            store.apply(&data, &[change])?;
+        }
+        Command::Vacation {
+            command: VacationCommand::List,
+        } => {
+            let vacations = list_vacations(&data);
+            if vacations.is_empty() {
+                println!("No vacations. Add one with `heat vacation add <start> [end]`.");
+            }
+            for (i, (habit, vacation, _)) in vacations.iter().enumerate() {
+                let name = habit.map_or("all habits", |index| data.habits[index].name.as_str());
+                println!("{:>3}  {}  {}", i + 1, vacation, name);
+            }
+        }
+        Command::Vacation {
+            command: VacationCommand::Rm { number },
+        } => {
+            let (habit, vacation, position) = list_vacations(&data)
+                .into_iter()
+                .nth(number.wrapping_sub(1))
+                .ok_or_else(|| invalid_input(format!("no vacation number {}", number)))?;
+            let change = match habit {
+                Some(index) => {
+                    let habit = &mut data.habits[index];
+                    habit.vacations.remove(position);
+                    println!("Removed vacation {} for {}", vacation, habit.name);
+                    Change::Habit(habit.id)
+                }
+                None => {
+                    data.vacations.remove(position);
+                    println!("Removed vacation {} for all habits", vacation);
+                    Change::Vacations
+                }
+            };
+            store.apply(&data, &[change])?;
+        }
+        Command::Archive { habit } => {
+            let index = find_habit(&data, &habit)?;
+            let habit = &mut data.habits[index];
+            if habit.is_archived() {
+                return Err(invalid_input(format!("\"{}\" is already archived", habit.name)));
+            }
+            habit.archived_on = Some(today);
+            println!("Archived \"{}\"; `heat unarchive` brings it back", habit.name);
+            let change = Change::Habit(habit.id);
+            store.apply(&data, &[change])?;
+        }
+        Command::Unarchive { habit } => {
+            let index = find_habit(&data, &habit)?;
+            let habit = &mut data.habits[index];
+            if !habit.is_archived() {
+                return Err(invalid_input(format!("\"{}\" isn't archived", habit.name)));
+            }
+            habit.archived_on = None;
+            println!("Restored \"{}\"", habit.name);
+            let change = Change::Habit(habit.id);
+            store.apply(&data, &[change])?;
+        }
+        Command::Rm { habit } => {
+            let index = find_habit(&data, &habit)?;
+            let habit = data.habits.remove(index);
+            println!("Deleted \"{}\"", habit.name);
+            store.apply(&data, &[Change::Removed(habit.id)])?;
+        }
+        Command::List { json, archived } => {
+            let habits: Vec<&Habit> = data.habits.iter().filter(|h| h.is_archived() == archived).collect();
+            if json {
+                return print_report(Report::new(habits, clock, &data.vacations));
+            }
+            if habits.is_empty() && archived {
+                println!("No archived habits.");
+            } else if habits.is_empty() {
+                println!("No habits yet. Add one with `heat add <name>`.");
+            }
+            let width = name_width(&data);
+            for habit in habits {
+                let mark = if habit.progress_on(today) >= 1.0 {
+                    "✓"
+                } else if habit.is_scheduled(today) && !habit.is_excused(today, &data.vacations) {
+                    "·"
+                } else {
+                    "-"
+                };
+                println!(
+                    "{} {:<width$}  {:>3} {} streak  {}",
+                    mark,
+                    habit.name,
+                    habit.current_streak(clock, &data.vacations),
+                    habit.streak_unit(),
+                    habit_details(habit, today),
+                    width = width
+                );
+            }
+        }
+        Command::Restore { backup: None } => {
+            let backups = storage::list_backups(store.path())?;
+            if backups.is_empty() {
+                println!("No backups in {}", storage::get_backup_dir(store.path()).display());
+            }
+            for (i, path) in backups.iter().enumerate() {
+                println!("{:>3}  {}", i + 1, path.display());
+            }
+        }
+        Command::Restore {
+            backup: Some(backup),
+        } => {
+            let path = match backup.parse::<usize>() {
+                Ok(number) => storage::list_backups(store.path())?
+                    .into_iter()
+                    .nth(number.wrapping_sub(1))
+                    .ok_or_else(|| invalid_input(format!("no backup number {}", number)))?,
+                Err(_) => PathBuf::from(backup),
+            };
+            let restored = storage::restore_backup(store, &path)?;
+            println!(
+                "Restored {} habits from {}",
+                restored.habits.len(),
+                path.display()
+            );
+        }
+        Command::Stats { habit, json } => {
+            let habits: Vec<&Habit> = match habit {
+                Some(query) => vec![&data.habits[find_habit(&data, &query)?]],
+                None => data.habits.iter().filter(|h| !h.is_archived()).collect(),
+            };
+            if json {
+                return print_report(Report::new(habits, clock, &data.vacations));
+            }
+            let width = name_width(&data);
+            println!(
+                "{:<width$}  {:>7}  {:>7}  {:>5}  {:>5}",
+                "Habit",
+                "Current",
+                "Best",
+                "Rate",
+                "Total",
+                width = width
+            );
+            for habit in &habits {
+                println!(
+                    "{:<width$}  {:>7}  {:>7}  {:>4}%  {:>5}",
+                    habit.name,
+                    habit.current_streak(clock, &data.vacations),
+                    habit.longest_streak(clock, &data.vacations),
+                    habit.completion_percentage(clock, &data.vacations),
+                    habit.completions.len(),
+                    width = width
+                );
+            }
+            // For habits to quit the streaks above are clean runs and the total counts slips
+            for habit in habits.iter().filter(|h| h.is_quit() && !h.completions.is_empty()) {
+                println!("\n{} slips by month", habit.name);
+                for ((year, month), count) in habit.relapses_by_month() {
+                    println!("  {}-{:02}  {:>3}", year, month, count);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Every vacation with the index of its habit (`None` for ones covering all habits) and its
+/// position in that habit's list: global ones first, then each habit's in order
+fn list_vacations(data: &AppData) -> Vec<(Option<usize>, Vacation, usize)> {
+    let global = data.vacations.iter().enumerate().map(|(i, v)| (None, *v, i));
+    let per_habit = data.habits.iter().enumerate().flat_map(|(index, habit)| {
+        habit
+            .vacations
+            .iter()
+            .enumerate()
+            .map(move |(i, v)| (Some(index), *v, i))
+    });
+    global.chain(per_habit).collect()
+}
+
+fn print_report(report: Report) -> io::Result<()> {
+    let json = report
+        .to_json()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    // Scripts may stop reading early, e.g. `heat list --json | head`, which isn't an error
+    match writeln!(io::stdout().lock(), "{}", json) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+fn name_width(data: &AppData) -> usize {
+    data.habits
+        .iter()
+        .map(|h| h.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(5)
+}
+
+/// Schedule (or "quit") and today's amount, shown after the streak in `heat list`
+fn habit_details(habit: &Habit, today: NaiveDate) -> String {
+    let kind = if habit.is_quit() {
+        habit.kind.to_string()
+    } else {
+        habit.schedule.to_string()
+    };
+    let mut details = vec![kind];
+    if let Some(progress) = habit.progress_label(today) {
+        details.push(progress);
+    }
+    details.join(", ")
+}
+
+/// One-line summary of a habit's state on `date` after a change
+fn describe_day(habit: &Habit, date: NaiveDate) -> String {
+    let status = match habit.progress_label(date) {
+        Some(progress) => progress,
+        None if habit.is_quit() && habit.completions.contains(&date) => "slipped".to_string(),
+        None if habit.is_quit() => "clean".to_string(),
+        None if habit.completions.contains(&date) => "done".to_string(),
+        None if habit.skipped.contains(&date) => "skipped".to_string(),
+        None => "not done".to_string(),
+    };
+    format!("{} on {}: {}", habit.name, date, status)
+}
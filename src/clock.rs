@@ -0,0 +1,98 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, Weekday};
+
+/// Where the current date and the user's calendar conventions come from,
+/// so date logic can be pinned to a fixed day in tests
+pub trait Clock {
+    /// The logical date right now
+    fn today(&self) -> NaiveDate;
+
+    /// First day of the week, for weekly goals and the heatmap layout
+    fn week_start(&self) -> Weekday;
+}
+
+/// The local wall clock, with each day starting at `day_start_hour` (0-23)
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    pub day_start_hour: u32,
+    pub week_start: Weekday,
+}
+
+impl Clock for SystemClock {
+    fn today(&self) -> NaiveDate {
+        logical_date(chrono::Local::now().naive_local(), self.day_start_hour)
+    }
+
+    fn week_start(&self) -> Weekday {
+        self.week_start
+    }
+}
+
+/// A clock that is always on the same date
+#[cfg(test)]
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock {
+    pub today: NaiveDate,
+    pub week_start: Weekday,
+}
+
+#[cfg(test)]
+impl FixedClock {
+    /// Stuck on `today`, with Sunday-first weeks
+    pub fn on(today: NaiveDate) -> Self {
+        Self {
+            today,
+            week_start: Weekday::Sun,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn today(&self) -> NaiveDate {
+        self.today
+    }
+
+    fn week_start(&self) -> Weekday {
+        self.week_start
+    }
+}
+
+/// Shorthand for a date in tests
+#[cfg(test)]
+pub fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+/// A new habit called `name`, created on `created_at`
+#[cfg(test)]
+pub fn habit_on(name: &str, created_at: NaiveDate) -> crate::data::Habit {
+    crate::data::Habit::new(name.to_string(), &FixedClock::on(created_at))
+}
+
+/// Data holding a new habit for each of `names`, all created on 2026-01-01
+#[cfg(test)]
+pub fn data_with(names: &[&str]) -> crate::data::AppData {
+    let mut data = crate::data::AppData::new();
+    data.habits = names.iter().map(|name| habit_on(name, date(2026, 1, 1))).collect();
+    data
+}
+
+/// The logical date for `now`: before `day_start_hour` it's still the previous day
+pub fn logical_date(now: NaiveDateTime, day_start_hour: u32) -> NaiveDate {
+    (now - Duration::hours(day_start_hour.min(23) as i64)).date()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn logical_date_before_day_start_is_previous_day() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let at = |h, m| date.and_hms_opt(h, m, 0).unwrap();
+
+        assert_eq!(logical_date(at(0, 30), 0), date);
+        assert_eq!(logical_date(at(0, 30), 4), NaiveDate::from_ymd_opt(2025, 12, 31).unwrap());
+        assert_eq!(logical_date(at(4, 0), 4), date);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::Weekday;
+use serde::Deserialize;
+
+use crate::storage::Backend;
+use crate::theme::{parse_color, Theme};
+
+/// Preferences read from `config.toml`. Every key is optional, and command-line
+/// flags and environment variables take precedence over the file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Data file to use instead of the default one in the data directory
+    pub data_file: Option<PathBuf>,
+    /// Storage backend for the data file
+    pub storage: Option<Backend>,
+    /// Hour (0-23) at which a new day starts
+    pub day_start_hour: Option<u32>,
+    /// First day of the week, for the heatmap and weekly goals
+    pub week_start: Option<Weekday>,
+    /// Most habit cards per row; by default as many as fit the terminal
+    pub grid_columns: Option<usize>,
+    /// Name of the color theme, built-in or from `themes`
+    pub theme: String,
+    /// User-defined themes by name
+    pub themes: BTreeMap<String, ThemeConfig>,
+    /// Show streak stats on the cards when the TUI opens
+    pub show_stats: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            data_file: None,
+            storage: None,
+            day_start_hour: None,
+            week_start: None,
+            grid_columns: None,
+            theme: "dark".to_string(),
+            themes: BTreeMap::new(),
+            show_stats: false,
+        }
+    }
+}
+
+/// A user-defined theme: a built-in theme to start from, and the colors to change.
+/// Colors are names ("green"), hex codes ("#3fb950") or "rgb(63, 185, 80)".
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub base: Option<String>,
+    pub accent: Option<String>,
+    pub muted: Option<String>,
+    pub done: Option<String>,
+    pub skipped: Option<String>,
+    pub error: Option<String>,
+    pub input: Option<String>,
+    pub highlight_text: Option<String>,
+}
+
+impl ThemeConfig {
+    fn resolve(&self) -> Result<Theme, String> {
+        let mut theme = match &self.base {
+            Some(base) => Theme::named(base).ok_or_else(|| format!("unknown base theme \"{}\"", base))?,
+            None => Theme::default(),
+        };
+        for (color, slot) in [
+            (&self.accent, &mut theme.accent),
+            (&self.muted, &mut theme.muted),
+            (&self.done, &mut theme.done),
+            (&self.skipped, &mut theme.skipped),
+            (&self.error, &mut theme.error),
+            (&self.input, &mut theme.input),
+            (&self.highlight_text, &mut theme.highlight_text),
+        ] {
+            if let Some(color) = color {
+                *slot = parse_color(color)?;
+            }
+        }
+        Ok(theme)
+    }
+}
+
+/// Where the config is read from unless `--config` or `HEAT_CONFIG` says otherwise
+pub fn get_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("heat").join("config.toml"))
+}
+
+fn invalid(path: &Path, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid config {}: {}", path.display(), message),
+    )
+}
+
+impl Config {
+    /// Read the config at `path`, or at the default location if `None`.
+    /// A missing file at the default location just means all defaults.
+    pub fn load(path: Option<&Path>) -> io::Result<Self> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match get_config_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !explicit => return Ok(Self::default()),
+            Err(e) => {
+                return Err(io::Error::new(
+                    e.kind(),
+                    format!("reading config {}: {}", path.display(), e),
+                ));
+            }
+        };
+
+        let mut config: Config =
+            toml::from_str(&contents).map_err(|e| invalid(&path, e.to_string().trim_end()))?;
+
+        if config.grid_columns == Some(0) {
+            return Err(invalid(&path, "grid_columns must be at least 1"));
+        }
+        if config.day_start_hour.is_some_and(|hour| hour > 23) {
+            return Err(invalid(&path, "day_start_hour must be between 0 and 23"));
+        }
+        for (name, theme) in &config.themes {
+            theme
+                .resolve()
+                .map_err(|e| invalid(&path, format!("theme \"{}\": {}", name, e)))?;
+        }
+        if !config.themes.contains_key(&config.theme) && Theme::named(&config.theme).is_none() {
+            let built_in: Vec<&str> = Theme::BUILT_IN.iter().map(|(name, _)| *name).collect();
+            return Err(invalid(
+                &path,
+                format!(
+                    "unknown theme \"{}\"; built-in themes are {}",
+                    config.theme,
+                    built_in.join(", ")
+                ),
+            ));
+        }
+        config.data_file = config.data_file.map(expand_home);
+
+        Ok(config)
+    }
+
+    /// The selected theme; user-defined themes win over built-in ones of the same name
+    pub fn theme(&self) -> Theme {
+        self.themes
+            .get(&self.theme)
+            .and_then(|theme| theme.resolve().ok())
+            .or_else(|| Theme::named(&self.theme))
+            .unwrap_or_default()
+    }
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: PathBuf) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path,
+    }
+}
@@ -1,121 +1,1073 @@
-use chrono::NaiveDate;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::clock::Clock;
+
+/// First day of the week containing `date`, for weeks starting on `week_start`
+pub fn start_of_week(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let days_into_week =
+        (date.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+    date - Duration::days(days_into_week as i64)
+}
+
+/// How often a habit is expected to be done
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Schedule {
+    #[default]
+    Daily,
+    /// Only on the listed days of the week
+    Weekdays { days: Vec<Weekday> },
+    /// A number of completions anywhere within each week
+    TimesPerWeek { times: u32 },
+    /// A number of completions anywhere within each month
+    TimesPerMonth { times: u32 },
+    /// Every `interval` days, counted from the habit's creation date
+    EveryNDays { interval: u32 },
+}
+
+impl Schedule {
+    /// Whether the schedule is made of individual due days (as opposed to weekly/monthly quotas)
+    pub fn is_day_based(&self) -> bool {
+        !matches!(self, Schedule::TimesPerWeek { .. } | Schedule::TimesPerMonth { .. })
+    }
+
+    /// Whether `date` is a due day. Quota schedules treat every day as eligible.
+    pub fn is_scheduled(&self, date: NaiveDate, anchor: NaiveDate) -> bool {
+        match self {
+            Schedule::Daily | Schedule::TimesPerWeek { .. } | Schedule::TimesPerMonth { .. } => true,
+            Schedule::Weekdays { days } => days.contains(&date.weekday()),
+            Schedule::EveryNDays { interval } => {
+                let interval = (*interval).max(1) as i64;
+                (date - anchor).num_days().rem_euclid(interval) == 0
+            }
+        }
+    }
+
+    /// Unit used when displaying streak lengths
+    pub fn streak_unit(&self) -> &'static str {
+        match self {
+            Schedule::TimesPerWeek { .. } => "week",
+            Schedule::TimesPerMonth { .. } => "month",
+            _ => "day",
+        }
+    }
+
+    /// Number of completions required per period for quota schedules
+    fn quota(&self) -> u32 {
+        match self {
+            Schedule::TimesPerWeek { times } | Schedule::TimesPerMonth { times } => (*times).max(1),
+            _ => 1,
+        }
+    }
+
+    /// First day of the quota period containing `date`, with weeks starting on `week_start`
+    fn period_start(&self, date: NaiveDate, week_start: Weekday) -> NaiveDate {
+        match self {
+            Schedule::TimesPerMonth { .. } => date.with_day(1).unwrap_or(date),
+            _ => start_of_week(date, week_start),
+        }
+    }
+
+    /// First day of the quota period following the one starting at `start`
+    fn next_period(&self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Schedule::TimesPerMonth { .. } => start
+                .checked_add_months(chrono::Months::new(1))
+                .unwrap_or(start + Duration::days(31)),
+            _ => start + Duration::days(7),
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Schedule::Daily => write!(f, "daily"),
+            Schedule::Weekdays { days } => {
+                let names: Vec<String> = days
+                    .iter()
+                    .map(|d| d.to_string().to_lowercase())
+                    .collect();
+                write!(f, "{}", names.join(","))
+            }
+            Schedule::TimesPerWeek { times } => write!(f, "{}x/week", times),
+            Schedule::TimesPerMonth { times } => write!(f, "{}x/month", times),
+            Schedule::EveryNDays { interval } => write!(f, "every {} days", interval),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    /// Parse "daily", "weekdays", "mon,wed,fri", "3x/week", "2x/month" or "every 3 days"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+
+        if s == "daily" || s == "every day" {
+            return Ok(Schedule::Daily);
+        }
+        if s == "weekdays" {
+            let days = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+            return Ok(Schedule::Weekdays { days });
+        }
+        if s == "weekends" {
+            return Ok(Schedule::Weekdays { days: vec![Weekday::Sat, Weekday::Sun] });
+        }
+
+        let parse_count = |n: &str| {
+            n.trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or_else(|| format!("invalid number \"{}\"", n.trim()))
+        };
+
+        if let Some(n) = s.strip_suffix("/week").or_else(|| s.strip_suffix(" per week")) {
+            let times = parse_count(n.trim_end_matches('x'))?;
+            // Only one completion counts per day, so more could never be met
+            if times > 7 {
+                return Err(format!("a week has only 7 days, so {}x/week can't be met", times));
+            }
+            return Ok(Schedule::TimesPerWeek { times });
+        }
+        if let Some(n) = s.strip_suffix("/month").or_else(|| s.strip_suffix(" per month")) {
+            let times = parse_count(n.trim_end_matches('x'))?;
+            if times > 31 {
+                return Err(format!("a month has at most 31 days, so {}x/month can't be met", times));
+            }
+            return Ok(Schedule::TimesPerMonth { times });
+        }
+        if let Some(rest) = s.strip_prefix("every ") {
+            let n = rest.trim_end_matches(" days").trim_end_matches(" day");
+            let interval = parse_count(n)?;
+            return Ok(if interval == 1 {
+                Schedule::Daily
+            } else {
+                Schedule::EveryNDays { interval }
+            });
+        }
+
+        let mut days = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let day = part
+                .parse::<Weekday>()
+                .map_err(|_| format!("unknown schedule \"{}\"", part))?;
+            if !days.contains(&day) {
+                days.push(day);
+            }
+        }
+        if days.is_empty() {
+            return Err("schedule is empty".to_string());
+        }
+        days.sort_by_key(|d| d.num_days_from_sunday());
+        Ok(Schedule::Weekdays { days })
+    }
+}
+
+/// Whether a habit is a simple yes/no check or a measured amount per day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HabitKind {
+    #[default]
+    Check,
+    /// A daily amount counted towards a target, e.g. 8 glasses of water
+    Measure { target: f64, unit: String },
+    /// Something to stay away from, e.g. smoking: completions record slips, and every
+    /// other day since tracking started counts as clean
+    Quit,
+}
+
+impl HabitKind {
+    /// Parse a target such as "8 glasses" or "30 pages"; an empty string means a plain check
+    /// habit and "quit" a habit to quit
+    pub fn parse_target(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(HabitKind::Check);
+        }
+        if s.eq_ignore_ascii_case("quit") {
+            return Ok(HabitKind::Quit);
+        }
+
+        let (number, unit) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let target = number
+            .parse::<f64>()
+            .ok()
+            .filter(|t| t.is_finite() && *t > 0.0)
+            .ok_or_else(|| format!("invalid target \"{}\"", number))?;
+
+        Ok(HabitKind::Measure {
+            target,
+            unit: unit.trim().to_string(),
+        })
+    }
+}
+
+impl fmt::Display for HabitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HabitKind::Check => Ok(()),
+            HabitKind::Measure { target, unit } if unit.is_empty() => {
+                write!(f, "{}", format_amount(*target))
+            }
+            HabitKind::Measure { target, unit } => write!(f, "{} {}", format_amount(*target), unit),
+            HabitKind::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+/// Format an amount without a trailing ".0" for whole numbers
+pub fn format_amount(amount: f64) -> String {
+    if amount.fract() == 0.0 {
+        format!("{}", amount as i64)
+    } else {
+        format!("{:.1}", amount)
+    }
+}
+
+/// A stretch of days off, `start` to `end` inclusive, during which missed days are excused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vacation {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl Vacation {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+}
+
+impl fmt::Display for Vacation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{} to {}", self.start, self.end)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Habit {
     pub id: Uuid,
     pub name: String,
     pub created_at: NaiveDate,
     pub completions: Vec<NaiveDate>,
+    #[serde(default)]
+    pub schedule: Schedule,
+    #[serde(default)]
+    pub kind: HabitKind,
+    /// Recorded amounts for measured habits; a day counts as completed once it reaches the target
+    #[serde(default)]
+    pub amounts: BTreeMap<NaiveDate, f64>,
+    /// Days deliberately skipped, e.g. rest days, which don't break streaks
+    #[serde(default)]
+    pub skipped: Vec<NaiveDate>,
+    /// Time off from this habit only; see `AppData::vacations` for time off from everything
+    #[serde(default)]
+    pub vacations: Vec<Vacation>,
+    /// Notes on individual days, e.g. "ran 5k, knee hurt"
+    #[serde(default)]
+    pub notes: BTreeMap<NaiveDate, String>,
+    /// Day the habit was archived: it's hidden from the grid, but its history is kept
+    #[serde(default)]
+    pub archived_on: Option<NaiveDate>,
 }
 
 impl Habit {
-    pub fn new(name: String) -> Self {
+    pub fn new(name: String, clock: &dyn Clock) -> Self {
         Self {
             id: Uuid::new_v4(),
             name,
-            created_at: chrono::Local::now().date_naive(),
+            created_at: clock.today(),
             completions: Vec::new(),
+            schedule: Schedule::Daily,
+            kind: HabitKind::Check,
+            amounts: BTreeMap::new(),
+            skipped: Vec::new(),
+            vacations: Vec::new(),
+            notes: BTreeMap::new(),
+            archived_on: None,
+        }
+    }
+
+    pub fn is_measured(&self) -> bool {
+        matches!(self.kind, HabitKind::Measure { .. })
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived_on.is_some()
+    }
+
+    pub fn is_quit(&self) -> bool {
+        self.kind == HabitKind::Quit
+    }
+
+    /// Unit used when displaying streak lengths; a clean run is always counted in days
+    pub fn streak_unit(&self) -> &'static str {
+        if self.is_quit() {
+            "day"
+        } else {
+            self.schedule.streak_unit()
         }
     }
 
-    /// Calculate current streak - consecutive days ending today or yesterday
-    pub fn current_streak(&self) -> u32 {
-        let today = chrono::Local::now().date_naive();
-        let mut completions: Vec<NaiveDate> = self.completions.clone();
-        completions.sort();
+    /// Amount recorded on `date`, or zero if none
+    pub fn amount_on(&self, date: NaiveDate) -> f64 {
+        self.amounts.get(&date).copied().unwrap_or(0.0)
+    }
 
-        if completions.is_empty() {
-            return 0;
+    /// Fraction of the day's target reached on `date`, between 0 and 1.
+    /// A habit to quit is fully done on every day without a slip.
+    pub fn progress_on(&self, date: NaiveDate) -> f64 {
+        let recorded = self.completions.contains(&date);
+        if self.is_quit() {
+            return if recorded { 0.0 } else { 1.0 };
+        }
+        if recorded {
+            return 1.0;
         }
+        match &self.kind {
+            HabitKind::Check | HabitKind::Quit => 0.0,
+            HabitKind::Measure { target, .. } => (self.amount_on(date) / target).clamp(0.0, 1.0),
+        }
+    }
 
-        // Start from today or yesterday if today isn't completed
-        let start_date = if completions.contains(&today) {
-            today
+    /// Amount logged against the target on `date`, e.g. "3/8 glasses"; `None` for other habits
+    pub fn progress_label(&self, date: NaiveDate) -> Option<String> {
+        match &self.kind {
+            HabitKind::Check | HabitKind::Quit => None,
+            HabitKind::Measure { target, unit } => {
+                let label = format!(
+                    "{}/{} {}",
+                    format_amount(self.amount_on(date)),
+                    format_amount(*target),
+                    unit
+                );
+                Some(label.trim_end().to_string())
+            }
+        }
+    }
+
+    /// Record the amount for `date`, marking the day completed once the target is reached.
+    /// Capped at the largest finite value, since JSON can't store infinity.
+    pub fn set_amount(&mut self, date: NaiveDate, amount: f64) {
+        if amount > 0.0 {
+            self.amounts.insert(date, amount.min(f64::MAX));
         } else {
-            let yesterday = today - chrono::Duration::days(1);
-            if completions.contains(&yesterday) {
-                yesterday
+            self.amounts.remove(&date);
+        }
+        self.sync_completion(date);
+    }
+
+    /// Change the habit kind, re-evaluating recorded amounts against the new target.
+    /// Recorded days mean slips for a habit to quit, so a habit with history can't
+    /// switch to or from one.
+    pub fn set_kind(&mut self, kind: HabitKind) -> Result<(), String> {
+        let quitting = kind == HabitKind::Quit;
+        if quitting != self.is_quit() && !self.completions.is_empty() {
+            return Err(if quitting {
+                "a habit with completions can't become a habit to quit".to_string()
             } else {
-                return 0;
-            }
+                "a habit with slips can only stay a habit to quit".to_string()
+            });
+        }
+        self.kind = kind;
+        let dates: Vec<NaiveDate> = self.amounts.keys().copied().collect();
+        for date in dates {
+            self.sync_completion(date);
+        }
+        Ok(())
+    }
+
+    /// Keep `completions` in line with the recorded amount for `date`
+    fn sync_completion(&mut self, date: NaiveDate) {
+        let done = match &self.kind {
+            HabitKind::Check | HabitKind::Quit => return,
+            HabitKind::Measure { target, .. } => self.amount_on(date) >= *target,
         };
+        let pos = self.completions.iter().position(|&d| d == date);
+        match (done, pos) {
+            (true, None) => self.completions.push(date),
+            (false, Some(pos)) => {
+                self.completions.remove(pos);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn note_on(&self, date: NaiveDate) -> Option<&str> {
+        self.notes.get(&date).map(String::as_str)
+    }
+
+    /// Set the note for `date`; an empty note removes it
+    pub fn set_note(&mut self, date: NaiveDate, note: &str) {
+        let note = note.trim();
+        if note.is_empty() {
+            self.notes.remove(&date);
+        } else {
+            self.notes.insert(date, note.to_string());
+        }
+    }
+
+    /// Whether the habit is due on `date` according to its schedule. A habit to quit
+    /// is kept every day.
+    pub fn is_scheduled(&self, date: NaiveDate) -> bool {
+        self.is_quit() || self.schedule.is_scheduled(date, self.created_at)
+    }
+
+    /// Earliest date that can contribute to streaks
+    pub fn first_tracked_day(&self) -> NaiveDate {
+        self.completions
+            .iter()
+            .copied()
+            .min()
+            .map_or(self.created_at, |d| d.min(self.created_at))
+    }
+
+    /// Whether a miss on `date` is excused: the day was skipped, or falls in one of this
+    /// habit's vacations or in one of `away` (vacations from every habit)
+    pub fn is_excused(&self, date: NaiveDate, away: &[Vacation]) -> bool {
+        self.skipped.contains(&date) || self.vacations.iter().chain(away).any(|v| v.contains(date))
+    }
+
+    /// Mark `date` as skipped, or clear the skip if it already was. Returns whether it's now skipped.
+    pub fn toggle_skip(&mut self, date: NaiveDate) -> bool {
+        if let Some(pos) = self.skipped.iter().position(|&d| d == date) {
+            self.skipped.remove(pos);
+            false
+        } else {
+            self.skipped.push(date);
+            true
+        }
+    }
 
+    /// Lengths of the clean runs of a habit to quit, in days: before the first slip, between
+    /// slips and since the last one up to `today`, which counts as clean unless it's a slip
+    fn clean_runs(&self, today: NaiveDate) -> Vec<u32> {
+        let first_day = self.first_tracked_day();
+        if today < first_day {
+            return Vec::new();
+        }
+        let mut slips: Vec<NaiveDate> = self.completions.iter().copied().filter(|&d| d <= today).collect();
+        slips.sort_unstable();
+        slips.dedup();
+
+        let mut runs = Vec::new();
+        let mut clean_from = first_day;
+        for slip in slips {
+            runs.push((slip - clean_from).num_days() as u32);
+            clean_from = slip + Duration::days(1);
+        }
+        runs.push(((today - clean_from).num_days() + 1).max(0) as u32);
+        runs
+    }
+
+    /// Number of slips in each month, keyed by (year, month), for a habit to quit
+    pub fn relapses_by_month(&self) -> BTreeMap<(i32, u32), u32> {
+        let mut months = BTreeMap::new();
+        for date in self.completions.iter().collect::<HashSet<_>>() {
+            *months.entry((date.year(), date.month())).or_insert(0) += 1;
+        }
+        months
+    }
+
+    /// Completions within each quota period, oldest first, up to the period containing `today`.
+    /// Excused days lower the period's quota in proportion, so time off doesn't cost a whole
+    /// week or month but a single skip doesn't waive it either.
+    fn periods(
+        &self,
+        from: NaiveDate,
+        today: NaiveDate,
+        week_start: Weekday,
+        away: &[Vacation],
+    ) -> Vec<Period> {
+        let done: HashSet<NaiveDate> = self.completions.iter().copied().collect();
+        let quota = self.schedule.quota();
+        let mut periods = Vec::new();
+        let mut start = self.schedule.period_start(from, week_start);
+
+        while start <= today {
+            let end = self.schedule.next_period(start);
+            let count = done.iter().filter(|&&d| d >= start && d < end).count() as u32;
+            let days = (end - start).num_days() as u32;
+            let open = start
+                .iter_days()
+                .take_while(|&d| d < end)
+                .filter(|&d| !self.is_excused(d, away))
+                .count() as u32;
+            // Rounded to the nearest whole completion, in u64 so a huge quota can't overflow
+            let (quota, open, days) = (quota as u64, open as u64, days as u64);
+            let quota = ((2 * quota * open + days) / (2 * days)) as u32;
+            periods.push(Period { count, quota });
+            start = end;
+        }
+
+        periods
+    }
+
+    /// Calculate current streak - consecutive scheduled days (or met weekly/monthly quotas)
+    /// ending today, or at the previous due day/period if the current one isn't done yet.
+    /// Excused days (see `is_excused`) neither count nor break it.
+    /// For a habit to quit it's the days since the last slip.
+    pub fn current_streak(&self, clock: &dyn Clock, away: &[Vacation]) -> u32 {
+        let today = clock.today();
+
+        if self.is_quit() {
+            return self.clean_runs(today).last().copied().unwrap_or(0);
+        }
+
+        if self.completions.is_empty() {
+            return 0;
+        }
+
+        if !self.schedule.is_day_based() {
+            let periods = self.periods(self.first_tracked_day(), today, clock.week_start(), away);
+            let mut periods = periods.iter().rev().peekable();
+
+            // The current period still has time left, so it only counts once met
+            if periods.peek().is_some_and(|p| !p.is_met()) {
+                periods.next();
+            }
+            let mut streak = 0;
+            for period in periods {
+                if period.is_met() {
+                    streak += 1;
+                } else if !period.is_off() {
+                    break;
+                }
+            }
+            return streak;
+        }
+
+        let done: HashSet<NaiveDate> = self.completions.iter().copied().collect();
+        let first_day = self.first_tracked_day();
         let mut streak = 0;
-        let mut current_date = start_date;
+        let mut current_date = today;
 
-        while completions.contains(&current_date) {
-            streak += 1;
-            current_date -= chrono::Duration::days(1);
+        // Today is still in progress, so an unfinished today doesn't break the streak
+        if self.is_scheduled(today) && !done.contains(&today) {
+            current_date -= Duration::days(1);
+        }
+
+        while current_date >= first_day {
+            if self.is_scheduled(current_date) {
+                if done.contains(&current_date) {
+                    streak += 1;
+                } else if !self.is_excused(current_date, away) {
+                    break;
+                }
+            }
+            current_date -= Duration::days(1);
         }
 
         streak
     }
 
-    /// Calculate longest streak ever achieved
-    pub fn longest_streak(&self) -> u32 {
-        let mut completions: Vec<NaiveDate> = self.completions.clone();
-        completions.sort();
+    /// Calculate longest streak ever achieved, or the longest clean run for a habit to quit
+    pub fn longest_streak(&self, clock: &dyn Clock, away: &[Vacation]) -> u32 {
+        let today = clock.today();
+
+        if self.is_quit() {
+            return self.clean_runs(today).into_iter().max().unwrap_or(0);
+        }
 
-        if completions.is_empty() {
+        if self.completions.is_empty() {
             return 0;
         }
 
-        let mut longest = 1;
-        let mut current = 1;
+        let first_day = self.first_tracked_day();
+        let last_day = self.completions.iter().copied().max().map_or(today, |d| d.max(today));
 
-        for i in 1..completions.len() {
-            let diff = completions[i].signed_duration_since(completions[i - 1]).num_days();
-            if diff == 1 {
-                current += 1;
-                longest = longest.max(current);
-            } else if diff > 1 {
-                current = 1;
+        if !self.schedule.is_day_based() {
+            let mut longest = 0;
+            let mut current = 0;
+            for period in self.periods(first_day, last_day, clock.week_start(), away) {
+                if period.is_met() {
+                    current += 1;
+                    longest = longest.max(current);
+                } else if !period.is_off() {
+                    current = 0;
+                }
             }
-            // diff == 0 means duplicate date, ignore
+            return longest;
+        }
+
+        let done: HashSet<NaiveDate> = self.completions.iter().copied().collect();
+        let mut longest = 0;
+        let mut current = 0;
+        let mut date = first_day;
+
+        while date <= last_day {
+            if self.is_scheduled(date) {
+                if done.contains(&date) {
+                    current += 1;
+                    longest = longest.max(current);
+                } else if date != today && !self.is_excused(date, away) {
+                    current = 0;
+                }
+            }
+            date += Duration::days(1);
         }
 
         longest
     }
 
-    /// Toggle today's completion status
-    pub fn toggle_today(&mut self) {
-        let today = chrono::Local::now().date_naive();
-        if let Some(pos) = self.completions.iter().position(|&d| d == today) {
+    /// Toggle the completion status of `date` (a slip for a habit to quit). Measured habits jump
+    /// between nothing and the full target.
+    pub fn toggle_date(&mut self, date: NaiveDate) {
+        if let HabitKind::Measure { target, .. } = self.kind {
+            let amount = if self.progress_on(date) >= 1.0 { 0.0 } else { target };
+            self.set_amount(date, amount);
+            return;
+        }
+        if let Some(pos) = self.completions.iter().position(|&d| d == date) {
             self.completions.remove(pos);
         } else {
-            self.completions.push(today);
+            self.completions.push(date);
+        }
+    }
+
+    /// Mark `date` as done, filling a measured habit's target (or adding `amount` to it if given).
+    /// For a habit to quit this records a slip.
+    pub fn mark_done(&mut self, date: NaiveDate, amount: Option<f64>) {
+        match (&self.kind, amount) {
+            (HabitKind::Measure { .. }, Some(amount)) => {
+                let total = self.amount_on(date) + amount;
+                self.set_amount(date, total);
+            }
+            (HabitKind::Measure { target, .. }, None) => {
+                let total = self.amount_on(date).max(*target);
+                self.set_amount(date, total);
+            }
+            (HabitKind::Check | HabitKind::Quit, _) => {
+                if !self.completions.contains(&date) {
+                    self.completions.push(date);
+                }
+            }
         }
     }
 
-    /// Calculate completion percentage since habit creation
-    pub fn completion_percentage(&self) -> u32 {
-        let today = chrono::Local::now().date_naive();
-        let total_days = (today - self.created_at).num_days() + 1;
-        if total_days <= 0 {
+    /// Remove any completion, recorded amount or skip on `date`
+    pub fn clear_date(&mut self, date: NaiveDate) {
+        self.completions.retain(|&d| d != date);
+        self.amounts.remove(&date);
+        self.skipped.retain(|&d| d != date);
+    }
+
+    /// Calculate completion percentage since habit creation, counting only due days
+    /// (or the share of each weekly/monthly quota that was met). Excused misses aren't counted.
+    /// For a habit to quit it's the share of clean days since tracking started.
+    pub fn completion_percentage(&self, clock: &dyn Clock, away: &[Vacation]) -> u32 {
+        let today = clock.today();
+        if self.is_quit() {
+            let runs = self.clean_runs(today);
+            let clean: u32 = runs.iter().sum();
+            let tracked = clean + runs.len().saturating_sub(1) as u32;
+            return (clean * 100).checked_div(tracked).unwrap_or(0);
+        }
+        if today < self.created_at {
             return 0;
         }
 
-        let completions_count = self
-            .completions
-            .iter()
-            .filter(|&date| *date >= self.created_at && *date <= today)
-            .count() as i64;
+        if !self.schedule.is_day_based() {
+            let periods = self.periods(self.created_at, today, clock.week_start(), away);
+            let met: i64 = periods.iter().map(|p| p.count.min(p.quota) as i64).sum();
+            let expected: i64 = periods.iter().map(|p| p.quota as i64).sum();
+            if expected == 0 {
+                return 0;
+            }
+            return ((met * 100) / expected) as u32;
+        }
+
+        let done: HashSet<NaiveDate> = self.completions.iter().copied().collect();
+        let mut total_days = 0;
+        let mut completions_count = 0;
+        let mut date = self.created_at;
+
+        while date <= today {
+            if self.is_scheduled(date) {
+                if done.contains(&date) {
+                    total_days += 1;
+                    completions_count += 1;
+                } else if !self.is_excused(date, away) {
+                    total_days += 1;
+                }
+            }
+            date += Duration::days(1);
+        }
+
+        if total_days == 0 {
+            return 0;
+        }
 
         ((completions_count * 100) / total_days) as u32
     }
 }
 
+/// Completions in one weekly or monthly quota period
+struct Period {
+    count: u32,
+    /// The schedule's quota, lowered for the period's excused days
+    quota: u32,
+}
+
+impl Period {
+    fn is_met(&self) -> bool {
+        self.quota > 0 && self.count >= self.quota
+    }
+
+    /// Excused for (almost) all of its days, so it neither counts towards a streak nor breaks one
+    fn is_off(&self) -> bool {
+        self.quota == 0
+    }
+}
+
+/// Version of the on-disk document layout; bump it and add a migration in `storage`
+/// whenever a change can't be read by older files through serde defaults alone, or when
+/// older builds couldn't read the new layout or would drop new fields on their next save.
+/// Version 2 added habits to quit, skipped days, vacations, notes and archiving.
+pub const SCHEMA_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppData {
+    pub version: u32,
     pub habits: Vec<Habit>,
+    /// Time off from every habit
+    #[serde(default)]
+    pub vacations: Vec<Vacation>,
 }
 
 impl AppData {
     pub fn new() -> Self {
         Self {
+            version: SCHEMA_VERSION,
             habits: Vec::new(),
+            vacations: Vec::new(),
+        }
+    }
+
+    /// Put habits in the order of `order`; ones missing from it keep their order at the end
+    pub fn reorder(&mut self, order: &[Uuid]) {
+        self.habits
+            .sort_by_key(|h| order.iter().position(|&id| id == h.id).unwrap_or(usize::MAX));
+    }
+
+    /// Swap the habit with `id` with the next (or with a negative `step`, previous) habit
+    /// that isn't archived. Returns its new index, or `None` if there's none to swap with.
+    pub fn move_habit(&mut self, id: Uuid, step: i64) -> Option<usize> {
+        let from = self.habits.iter().position(|h| h.id == id)?;
+        let line: Vec<usize> = (0..self.habits.len())
+            .filter(|&i| i == from || !self.habits[i].is_archived())
+            .collect();
+        let at = line.iter().position(|&i| i == from)? as i64;
+        let to = *line.get(usize::try_from(at + step).ok()?)?;
+        self.habits.swap(from, to);
+        Some(to)
+    }
+
+    /// Ids of all habits, in order
+    pub fn order(&self) -> Vec<Uuid> {
+        self.habits.iter().map(|h| h.id).collect()
+    }
+
+    /// Find a habit by id, id prefix, or case-insensitive name (or unique name prefix)
+    pub fn find_habit(&self, query: &str) -> Result<usize, String> {
+        let query = query.trim();
+        let lower = query.to_lowercase();
+
+        if let Some(index) = self
+            .habits
+            .iter()
+            .position(|h| h.id.to_string() == lower || h.name.to_lowercase() == lower)
+        {
+            return Ok(index);
+        }
+
+        let matches: Vec<usize> = self
+            .habits
+            .iter()
+            .enumerate()
+            .filter(|(_, h)| {
+                h.name.to_lowercase().starts_with(&lower)
+                    || (lower.len() >= 4 && h.id.to_string().starts_with(&lower))
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        match matches.as_slice() {
+            [index] => Ok(*index),
+            [] => Err(format!("no habit matches \"{}\"", query)),
+            _ => {
+                let names: Vec<&str> = matches.iter().map(|&i| self.habits[i].name.as_str()).collect();
+                Err(format!("\"{}\" is ambiguous: {}", query, names.join(", ")))
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{date, habit_on, FixedClock};
+
+    fn habit(created_at: NaiveDate, schedule: Schedule, completions: &[NaiveDate]) -> Habit {
+        let mut habit = habit_on("Test", created_at);
+        habit.schedule = schedule;
+        habit.completions = completions.to_vec();
+        habit
+    }
+
+    #[test]
+    fn new_habit_is_created_on_clock_date() {
+        let habit = Habit::new("Read".to_string(), &FixedClock::on(date(2025, 12, 31)));
+        assert_eq!(habit.created_at, date(2025, 12, 31));
+    }
+
+    #[test]
+    fn daily_streak_spans_year_boundary() {
+        let h = habit(
+            date(2025, 12, 29),
+            Schedule::Daily,
+            &[date(2025, 12, 30), date(2025, 12, 31), date(2026, 1, 1)],
+        );
+        let clock = FixedClock::on(date(2026, 1, 1));
+        assert_eq!(h.current_streak(&clock, &[]), 3);
+        assert_eq!(h.longest_streak(&clock, &[]), 3);
+    }
+
+    #[test]
+    fn unfinished_today_keeps_streak() {
+        let h = habit(
+            date(2025, 12, 30),
+            Schedule::Daily,
+            &[date(2025, 12, 30), date(2025, 12, 31)],
+        );
+        let clock = FixedClock::on(date(2026, 1, 1));
+        assert_eq!(h.current_streak(&clock, &[]), 2);
+        assert_eq!(h.longest_streak(&clock, &[]), 2);
+    }
+
+    #[test]
+    fn missed_day_breaks_streak() {
+        let h = habit(
+            date(2026, 1, 28),
+            Schedule::Daily,
+            &[date(2026, 1, 28), date(2026, 1, 29), date(2026, 1, 31), date(2026, 2, 1)],
+        );
+        let clock = FixedClock::on(date(2026, 2, 2));
+        assert_eq!(h.current_streak(&clock, &[]), 2);
+        assert_eq!(h.longest_streak(&clock, &[]), 2);
+
+        let clock = FixedClock::on(date(2026, 2, 3));
+        assert_eq!(h.current_streak(&clock, &[]), 0);
+    }
+
+    #[test]
+    fn daily_streak_spans_leap_day() {
+        let h = habit(
+            date(2024, 2, 28),
+            Schedule::Daily,
+            &[date(2024, 2, 28), date(2024, 2, 29), date(2024, 3, 1)],
+        );
+        let clock = FixedClock::on(date(2024, 3, 1));
+        assert_eq!(h.current_streak(&clock, &[]), 3);
+        assert_eq!(h.completion_percentage(&clock, &[]), 100);
+    }
+
+    #[test]
+    fn weekday_streak_skips_weekend() {
+        let days = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+        // 2026-01-02 is a Friday and 2026-01-05 the following Monday
+        let h = habit(
+            date(2026, 1, 1),
+            Schedule::Weekdays { days },
+            &[date(2026, 1, 1), date(2026, 1, 2), date(2026, 1, 5)],
+        );
+        let clock = FixedClock::on(date(2026, 1, 5));
+        assert_eq!(h.current_streak(&clock, &[]), 3);
+        assert_eq!(h.completion_percentage(&clock, &[]), 100);
+    }
+
+    #[test]
+    fn weekly_quota_spans_year_boundary() {
+        // The week starting Sunday 2025-12-28 runs into 2026
+        let h = habit(
+            date(2025, 12, 21),
+            Schedule::TimesPerWeek { times: 2 },
+            &[date(2025, 12, 22), date(2025, 12, 23), date(2025, 12, 29), date(2026, 1, 1)],
+        );
+        assert_eq!(h.current_streak(&FixedClock::on(date(2026, 1, 2)), &[]), 2);
+
+        // A new week that isn't met yet doesn't break the streak
+        assert_eq!(h.current_streak(&FixedClock::on(date(2026, 1, 5)), &[]), 2);
+        assert_eq!(h.longest_streak(&FixedClock::on(date(2026, 1, 5)), &[]), 2);
+
+        // A week that ended unmet does
+        assert_eq!(h.current_streak(&FixedClock::on(date(2026, 1, 11)), &[]), 0);
+    }
+
+    #[test]
+    fn weekly_quota_follows_week_start() {
+        // A Saturday and the Sunday after it: split across Sunday-first weeks, one ISO week
+        let h = habit(
+            date(2026, 1, 1),
+            Schedule::TimesPerWeek { times: 2 },
+            &[date(2026, 1, 3), date(2026, 1, 4)],
+        );
+        let sunday_first = FixedClock::on(date(2026, 1, 5));
+        let monday_first = FixedClock {
+            week_start: Weekday::Mon,
+            ..sunday_first
+        };
+        assert_eq!(h.current_streak(&sunday_first, &[]), 0);
+        assert_eq!(h.current_streak(&monday_first, &[]), 1);
+        assert_eq!(h.longest_streak(&monday_first, &[]), 1);
+    }
+
+    #[test]
+    fn quota_can_be_met_within_its_period() {
+        assert!(matches!("7x/week".parse(), Ok(Schedule::TimesPerWeek { times: 7 })));
+        assert!("8x/week".parse::<Schedule>().is_err());
+        assert!(matches!("31x/month".parse(), Ok(Schedule::TimesPerMonth { times: 31 })));
+        assert!("32x/month".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn start_of_week_respects_week_start() {
+        // 2026-01-01 is a Thursday
+        assert_eq!(start_of_week(date(2026, 1, 1), Weekday::Sun), date(2025, 12, 28));
+        assert_eq!(start_of_week(date(2026, 1, 1), Weekday::Mon), date(2025, 12, 29));
+        assert_eq!(start_of_week(date(2026, 1, 1), Weekday::Thu), date(2026, 1, 1));
+    }
+
+    #[test]
+    fn monthly_quota_streak_and_percentage() {
+        let h = habit(
+            date(2025, 11, 1),
+            Schedule::TimesPerMonth { times: 2 },
+            &[
+                date(2025, 11, 3),
+                date(2025, 11, 30),
+                date(2025, 12, 1),
+                date(2025, 12, 31),
+                date(2026, 1, 10),
+            ],
+        );
+        let clock = FixedClock::on(date(2026, 1, 15));
+        assert_eq!(h.current_streak(&clock, &[]), 2);
+        assert_eq!(h.longest_streak(&clock, &[]), 2);
+        // 2 + 2 + 1 of 6 expected completions
+        assert_eq!(h.completion_percentage(&clock, &[]), 83);
+    }
+
+    #[test]
+    fn percentage_counts_only_days_since_creation() {
+        let h = habit(
+            date(2025, 12, 30),
+            Schedule::Daily,
+            &[date(2025, 12, 30), date(2026, 1, 1)],
+        );
+        assert_eq!(h.completion_percentage(&FixedClock::on(date(2026, 1, 1)), &[]), 66);
+        assert_eq!(h.completion_percentage(&FixedClock::on(date(2025, 12, 29)), &[]), 0);
+    }
+
+    #[test]
+    fn skipped_days_and_vacations_keep_streak() {
+        let mut h = habit(
+            date(2026, 1, 1),
+            Schedule::Daily,
+            &[date(2026, 1, 1), date(2026, 1, 3), date(2026, 1, 7)],
+        );
+        h.toggle_skip(date(2026, 1, 2));
+        let away = [Vacation {
+            start: date(2026, 1, 4),
+            end: date(2026, 1, 6),
+        }];
+        let clock = FixedClock::on(date(2026, 1, 7));
+        assert_eq!(h.current_streak(&clock, &away), 3);
+        assert_eq!(h.longest_streak(&clock, &away), 3);
+        // Excused days aren't expected completions
+        assert_eq!(h.completion_percentage(&clock, &away), 100);
+
+        // Without the vacation the missed days count again
+        assert_eq!(h.current_streak(&clock, &[]), 1);
+    }
+
+    #[test]
+    fn excused_days_lower_the_quota_of_their_period() {
+        let days = |month: u32, count: u32| (1..=count).map(move |d| date(2026, month, d));
+        let completions: Vec<NaiveDate> = (1..=20)
+            .map(|d| date(2025, 12, d))
+            .chain(days(1, 5))
+            .chain(days(2, 20))
+            .collect();
+        let mut h = habit(date(2025, 12, 1), Schedule::TimesPerMonth { times: 20 }, &completions);
+        let clock = FixedClock::on(date(2026, 3, 10));
+
+        // One skipped day still leaves 19 due in January, which 5 completions don't meet
+        h.toggle_skip(date(2026, 1, 31));
+        assert_eq!(h.current_streak(&clock, &[]), 1);
+        assert_eq!(h.longest_streak(&clock, &[]), 1);
+        // January's completions still count, against its lowered quota
+        assert_eq!(h.completion_percentage(&clock, &[]), (45 * 100 / 79) as u32);
+
+        // Away for the whole month, nothing is due and the streak carries over
+        let away = [Vacation {
+            start: date(2026, 1, 1),
+            end: date(2026, 1, 31),
+        }];
+        assert_eq!(h.current_streak(&clock, &away), 2);
+    }
+
+    #[test]
+    fn quit_habit_counts_days_since_last_slip() {
+        let mut h = habit(date(2026, 1, 1), Schedule::Daily, &[]);
+        h.set_kind(HabitKind::Quit).unwrap();
+        let clock = FixedClock::on(date(2026, 1, 10));
+        assert_eq!(h.current_streak(&clock, &[]), 10);
+
+        h.mark_done(date(2026, 1, 6), None);
+        h.mark_done(date(2026, 1, 8), None);
+        assert_eq!(h.current_streak(&clock, &[]), 2);
+        assert_eq!(h.longest_streak(&clock, &[]), 5);
+        // 8 of 10 days clean
+        assert_eq!(h.completion_percentage(&clock, &[]), 80);
+        assert_eq!(h.relapses_by_month().get(&(2026, 1)), Some(&2));
+
+        assert!(h.set_kind(HabitKind::Check).is_err());
+    }
+
+    #[test]
+    fn empty_habit_has_no_streak() {
+        let h = habit(date(2026, 1, 1), Schedule::Daily, &[]);
+        let clock = FixedClock::on(date(2026, 3, 1));
+        assert_eq!(h.current_streak(&clock, &[]), 0);
+        assert_eq!(h.longest_streak(&clock, &[]), 0);
+        assert_eq!(h.completion_percentage(&clock, &[]), 0);
+    }
+
+    #[test]
+    fn amounts_stay_finite() {
+        let mut h = habit(date(2026, 1, 1), Schedule::Daily, &[]);
+        h.set_kind(HabitKind::Measure {
+            target: 8.0,
+            unit: "glasses".to_string(),
+        })
+        .unwrap();
+        h.mark_done(date(2026, 1, 1), Some(f64::MAX));
+        h.mark_done(date(2026, 1, 1), Some(f64::MAX));
+        assert_eq!(h.amount_on(date(2026, 1, 1)), f64::MAX);
+
+        h.set_amount(date(2026, 1, 1), f64::NAN);
+        assert_eq!(h.amount_on(date(2026, 1, 1)), 0.0);
+    }
+}
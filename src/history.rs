@@ -0,0 +1,341 @@
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::data::{AppData, Habit, HabitKind, Schedule};
+use crate::storage::Change;
+
+/// Edits kept for undo; older ones are forgotten
+const HISTORY_LIMIT: usize = 100;
+
+/// Everything recorded about a habit on one day
+#[derive(Debug, Clone, PartialEq)]
+pub struct Day {
+    done: bool,
+    amount: Option<f64>,
+    skipped: bool,
+    note: Option<String>,
+}
+
+impl Day {
+    pub fn of(habit: &Habit, date: NaiveDate) -> Self {
+        Self {
+            done: habit.completions.contains(&date),
+            amount: habit.amounts.get(&date).copied(),
+            skipped: habit.skipped.contains(&date),
+            note: habit.note_on(date).map(str::to_string),
+        }
+    }
+
+    /// Make `date` look like this again, leaving the habit's other days alone
+    fn restore(&self, habit: &mut Habit, date: NaiveDate) {
+        habit.completions.retain(|&d| d != date);
+        if self.done {
+            habit.completions.push(date);
+        }
+        match self.amount {
+            Some(amount) => habit.amounts.insert(date, amount),
+            None => habit.amounts.remove(&date),
+        };
+        habit.skipped.retain(|&d| d != date);
+        if self.skipped {
+            habit.skipped.push(date);
+        }
+        habit.set_note(date, self.note.as_deref().unwrap_or_default());
+    }
+}
+
+/// One edit made in the TUI, as the values it changed from and to. Undo and redo set
+/// just those values on the data as it is then, so changes made outside the session
+/// in the meantime (say a completion on another day) survive.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    /// A day's completion, amount, skip or note
+    Day {
+        habit: Uuid,
+        date: NaiveDate,
+        before: Day,
+        after: Day,
+    },
+    Rename {
+        habit: Uuid,
+        before: String,
+        after: String,
+    },
+    Schedule {
+        habit: Uuid,
+        before: Schedule,
+        after: Schedule,
+    },
+    Kind {
+        habit: Uuid,
+        before: HabitKind,
+        after: HabitKind,
+    },
+    /// Archived, restored from the archive, or both ways around
+    Archive {
+        habit: Uuid,
+        before: Option<NaiveDate>,
+        after: Option<NaiveDate>,
+    },
+    /// Added (or with `added` unset, deleted) at `position`. Taking the habit away keeps
+    /// it as it was then, to put back.
+    Presence { habit: Habit, position: usize, added: bool },
+    /// Swapped with the neighbouring habit in the grid; `step` is -1 or 1
+    Move { habit: Uuid, step: i64 },
+}
+
+impl Operation {
+    /// Id of the habit the edit touched, to select after undoing or redoing it
+    fn habit(&self) -> Uuid {
+        match self {
+            Operation::Day { habit, .. }
+            | Operation::Rename { habit, .. }
+            | Operation::Schedule { habit, .. }
+            | Operation::Kind { habit, .. }
+            | Operation::Archive { habit, .. }
+            | Operation::Move { habit, .. } => *habit,
+            Operation::Presence { habit, .. } => habit.id,
+        }
+    }
+
+    /// Make the edit again (`forward`) or revert it, returning the changes to store
+    fn apply(&mut self, data: &mut AppData, forward: bool) -> Result<Vec<Change>, String> {
+        let change = match self {
+            Operation::Day {
+                habit,
+                date,
+                before,
+                after,
+            } => {
+                let habit = find(data, *habit)?;
+                pick(forward, &*before, &*after).restore(habit, *date);
+                // Only the completion flipped, so store just that day
+                if before.skipped == after.skipped && before.note == after.note {
+                    Change::for_day(habit, *date)
+                } else {
+                    Change::Habit(habit.id)
+                }
+            }
+            Operation::Rename { habit, before, after } => {
+                let habit = find(data, *habit)?;
+                habit.name = pick(forward, &*before, &*after).clone();
+                Change::Habit(habit.id)
+            }
+            Operation::Schedule { habit, before, after } => {
+                let habit = find(data, *habit)?;
+                habit.schedule = pick(forward, &*before, &*after).clone();
+                Change::Habit(habit.id)
+            }
+            Operation::Kind { habit, before, after } => {
+                let habit = find(data, *habit)?;
+                habit.set_kind(pick(forward, &*before, &*after).clone())?;
+                Change::Habit(habit.id)
+            }
+            Operation::Archive { habit, before, after } => {
+                let habit = find(data, *habit)?;
+                habit.archived_on = *pick(forward, &*before, &*after);
+                Change::Habit(habit.id)
+            }
+            Operation::Presence { habit, position, added } => {
+                let existing = data.habits.iter().position(|h| h.id == habit.id);
+                return match (*added == forward, existing) {
+                    (true, None) => {
+                        data.habits.insert((*position).min(data.habits.len()), habit.clone());
+                        Ok(vec![Change::Habit(habit.id), Change::Order])
+                    }
+                    (false, Some(index)) => {
+                        *habit = data.habits.remove(index);
+                        *position = index;
+                        Ok(vec![Change::Removed(habit.id)])
+                    }
+                    (true, Some(_)) => Err("the habit is already there".to_string()),
+                    (false, None) => Err("the habit no longer exists".to_string()),
+                };
+            }
+            Operation::Move { habit, step } => {
+                data.move_habit(*habit, pick(forward, -*step, *step))
+                    .ok_or("there's no habit to swap places with")?;
+                Change::Order
+            }
+        };
+        Ok(vec![change])
+    }
+}
+
+fn find(data: &mut AppData, id: Uuid) -> Result<&mut Habit, String> {
+    data.habits
+        .iter_mut()
+        .find(|h| h.id == id)
+        .ok_or_else(|| "the habit no longer exists".to_string())
+}
+
+/// The value an edit sets when made (`forward`) or reverted
+fn pick<T>(forward: bool, before: T, after: T) -> T {
+    if forward { after } else { before }
+}
+
+/// One edit, with what to call it in the footer
+#[derive(Debug, Clone)]
+struct Edit {
+    /// e.g. "toggle Read on 2026-10-17"
+    description: String,
+    operation: Operation,
+}
+
+/// What an undo or redo did: the edit's description, and either the habit it touched
+/// and the changes to store, or why it couldn't be done
+pub type Outcome = (String, Result<(Uuid, Vec<Change>), String>);
+
+/// Undo and redo stacks for the session
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl History {
+    /// Remember an edit that was just made. A new edit makes the undone ones unreachable,
+    /// so they're dropped.
+    pub fn record(&mut self, description: String, operation: Operation) {
+        self.undo.push(Edit {
+            description,
+            operation,
+        });
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Revert the last edit. One that no longer applies, e.g. to a habit deleted
+    /// elsewhere, is dropped.
+    pub fn undo(&mut self, data: &mut AppData) -> Option<Outcome> {
+        let mut edit = self.undo.pop()?;
+        let result = edit.operation.apply(data, false);
+        let outcome = (edit.description.clone(), result.map(|c| (edit.operation.habit(), c)));
+        if outcome.1.is_ok() {
+            self.redo.push(edit);
+        }
+        Some(outcome)
+    }
+
+    /// Make the last undone edit again
+    pub fn redo(&mut self, data: &mut AppData) -> Option<Outcome> {
+        let mut edit = self.redo.pop()?;
+        let result = edit.operation.apply(data, true);
+        let outcome = (edit.description.clone(), result.map(|c| (edit.operation.habit(), c)));
+        if outcome.1.is_ok() {
+            self.undo.push(edit);
+        }
+        Some(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{data_with, date};
+
+    fn names(data: &AppData) -> Vec<&str> {
+        data.habits.iter().map(|h| h.name.as_str()).collect()
+    }
+
+    fn rename(history: &mut History, data: &mut AppData, name: &str) {
+        let operation = Operation::Rename {
+            habit: data.habits[0].id,
+            before: std::mem::replace(&mut data.habits[0].name, name.to_string()),
+            after: name.to_string(),
+        };
+        history.record(format!("rename to {}", name), operation);
+    }
+
+    #[test]
+    fn undo_and_redo_a_rename() {
+        let mut data = data_with(&["Read"]);
+        let mut history = History::default();
+        let id = data.habits[0].id;
+        rename(&mut history, &mut data, "Write");
+
+        let (description, result) = history.undo(&mut data).unwrap();
+        assert_eq!(description, "rename to Write");
+        let (habit, changes) = result.unwrap();
+        assert_eq!(habit, id);
+        assert!(matches!(changes[..], [Change::Habit(changed)] if changed == id));
+        assert_eq!(names(&data), ["Read"]);
+
+        history.redo(&mut data).unwrap().1.unwrap();
+        assert_eq!(names(&data), ["Write"]);
+        assert!(history.redo(&mut data).is_none());
+    }
+
+    #[test]
+    fn undo_keeps_changes_made_elsewhere() {
+        let mut data = data_with(&["Read"]);
+        let mut history = History::default();
+        let habit = &mut data.habits[0];
+        let before = Day::of(habit, date(2026, 1, 2));
+        habit.toggle_date(date(2026, 1, 2));
+        let operation = Operation::Day {
+            habit: habit.id,
+            date: date(2026, 1, 2),
+            before,
+            after: Day::of(habit, date(2026, 1, 2)),
+        };
+        history.record("toggle Read on 2026-01-02".to_string(), operation);
+
+        // Another process marks the day before, and the session reloads
+        data.habits[0].completions.push(date(2026, 1, 1));
+
+        let (_, result) = history.undo(&mut data).unwrap();
+        assert!(matches!(result.unwrap().1[..], [Change::Completion { done: false, .. }]));
+        assert_eq!(data.habits[0].completions, [date(2026, 1, 1)]);
+    }
+
+    #[test]
+    fn undo_of_a_habit_deleted_elsewhere_is_dropped() {
+        let mut data = data_with(&["Read"]);
+        let mut history = History::default();
+        rename(&mut history, &mut data, "Write");
+        data.habits.clear();
+
+        let (_, result) = history.undo(&mut data).unwrap();
+        assert!(result.is_err());
+        assert!(data.habits.is_empty());
+        assert!(history.redo(&mut data).is_none());
+    }
+
+    #[test]
+    fn undoing_a_delete_puts_the_habit_back_in_place() {
+        let mut data = data_with(&["Read", "Run", "Sleep"]);
+        let mut history = History::default();
+        let habit = data.habits.remove(1);
+        let id = habit.id;
+        let operation = Operation::Presence {
+            habit,
+            position: 1,
+            added: false,
+        };
+        history.record("delete Run".to_string(), operation);
+
+        history.undo(&mut data).unwrap().1.unwrap();
+        assert_eq!(names(&data), ["Read", "Run", "Sleep"]);
+
+        let (_, result) = history.redo(&mut data).unwrap();
+        assert_eq!(names(&data), ["Read", "Sleep"]);
+        assert!(matches!(result.unwrap().1[..], [Change::Removed(removed)] if removed == id));
+    }
+
+    #[test]
+    fn a_new_edit_drops_the_undone_ones() {
+        let mut data = data_with(&["Read"]);
+        let mut history = History::default();
+        rename(&mut history, &mut data, "Write");
+        rename(&mut history, &mut data, "Draw");
+        history.undo(&mut data).unwrap().1.unwrap();
+        rename(&mut history, &mut data, "Sing");
+
+        assert!(history.redo(&mut data).is_none());
+        history.undo(&mut data).unwrap().1.unwrap();
+        assert_eq!(names(&data), ["Write"]);
+    }
+}
@@ -1,22 +1,72 @@
 mod app;
+mod cli;
+mod clock;
+mod config;
 mod data;
+mod history;
+mod report;
 mod storage;
+mod theme;
 mod ui;
 
 use std::io;
+use std::time::Duration;
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
+use chrono::Weekday;
+use clap::Parser;
 use ratatui::prelude::*;
 
 use app::{App, InputMode};
+use cli::Cli;
+use clock::SystemClock;
+use config::Config;
+use storage::{FileWatcher, Storage};
+
+/// How long to wait for input before checking for outside changes and a new day
+const TICK_RATE: Duration = Duration::from_millis(250);
 
 fn main() -> io::Result<()> {
-    let app_data = storage::load_data()?;
-    let mut app = App::new(app_data);
+    let cli = Cli::parse();
+    let config = Config::load(cli.config.as_deref()).unwrap_or_else(|e| exit_with_error(e));
+
+    // Flags and environment variables win over the config file
+    let clock = SystemClock {
+        day_start_hour: cli.day_start.or(config.day_start_hour).unwrap_or(0),
+        week_start: cli.week_start.or(config.week_start).unwrap_or(Weekday::Sun),
+    };
+    let backend = cli.storage.or(config.storage).unwrap_or_default();
+
+    let data_path = match (cli.data, &cli.profile) {
+        (Some(path), _) => Some(path),
+        (None, Some(profile)) => Some(storage::get_profile_path(profile, backend)),
+        (None, None) => config.data_file.clone(),
+    };
+
+    let mut store = storage::open(backend, data_path).unwrap_or_else(|e| exit_with_error(e));
+
+    // Subcommands run non-interactively; only a bare `heat` opens the TUI
+    if let Some(command) = cli.command {
+        if let Err(e) = cli::run(command, store.as_mut(), &clock) {
+            exit_with_error(e);
+        }
+        return Ok(());
+    }
+
+    // Only one interactive session edits a file at a time; later ones are read-only
+    let lock = storage::try_lock(store.path()).unwrap_or_else(|e| exit_with_error(e));
+
+    let app_data = store.load().unwrap_or_else(|e| exit_with_error(e));
+    let mut app = App::new(app_data, Box::new(clock));
+    app.read_only = lock.is_none();
+    app.profile = cli.profile;
+    app.max_columns = config.grid_columns;
+    app.theme = config.theme();
+    app.show_stats = config.show_stats;
 
     // Setup terminal
     enable_raw_mode()?;
@@ -24,7 +74,7 @@ fn main() -> io::Result<()> {
     let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
 
     // Main loop
-    let result = run(&mut terminal, &mut app);
+    let result = run(&mut terminal, &mut app, store.as_mut());
 
     // Restore terminal
     disable_raw_mode()?;
@@ -33,78 +83,190 @@ fn main() -> io::Result<()> {
     result
 }
 
-fn run(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> io::Result<()> {
+fn exit_with_error(e: io::Error) -> ! {
+    eprintln!("heat: {}", e);
+    std::process::exit(1);
+}
+
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    store: &mut dyn Storage,
+) -> io::Result<()> {
+    // Live reload is a convenience; without a watcher, external edits still merge on save
+    let watcher = FileWatcher::new(store.path()).ok();
+
+    let mut needs_redraw = true;
+
     loop {
-        // Adjust scroll offset for visible area
-        let main_area_height = terminal.size()?.height.saturating_sub(1); // minus footer
-        app.adjust_scroll(main_area_height, ui::card_height(app.show_stats));
-
-        terminal.draw(|frame| ui::render(frame, app))?;
-
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                match app.input_mode {
-                    InputMode::Normal => match key.code {
-                        KeyCode::Char('q') => app.quit(),
-                        KeyCode::Char('j') => app.select_down(),
-                        KeyCode::Char('k') => app.select_up(),
-                        KeyCode::Char('h') => app.select_left(),
-                        KeyCode::Char('l') => app.select_right(),
-                        KeyCode::Char('g') => app.select_first(),
-                        KeyCode::Char('G') => app.select_last(),
-                        KeyCode::Char('a') => app.start_adding(),
-                        KeyCode::Char('r') => app.start_renaming(),
-                        KeyCode::Char('D') => app.start_deleting(),
-                        KeyCode::Char('s') => app.toggle_stats(),
-                        KeyCode::Enter => {
-                            app.toggle_today();
-                            storage::save_data(&app.data)?;
-                        }
-                        _ => {}
-                    },
-                    InputMode::Adding => match key.code {
-                        KeyCode::Enter => {
-                            app.confirm_add();
-                            storage::save_data(&app.data)?;
-                        }
-                        KeyCode::Esc => app.cancel_input(),
-                        KeyCode::Backspace => {
-                            app.input_buffer.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            app.input_buffer.push(c);
-                        }
-                        _ => {}
-                    },
-                    InputMode::Renaming => match key.code {
-                        KeyCode::Enter => {
-                            app.confirm_rename();
-                            storage::save_data(&app.data)?;
-                        }
-                        KeyCode::Esc => app.cancel_input(),
-                        KeyCode::Backspace => {
-                            app.input_buffer.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            app.input_buffer.push(c);
-                        }
-                        _ => {}
-                    },
-                    InputMode::Deleting => match key.code {
-                        KeyCode::Char('y') => {
-                            app.confirm_delete();
-                            storage::save_data(&app.data)?;
-                        }
-                        KeyCode::Char('n') | KeyCode::Esc => app.cancel_delete(),
-                        _ => {}
-                    },
+        if needs_redraw {
+            // Fit the grid to the terminal, then adjust scroll offset for visible area
+            let size = terminal.size()?;
+            app.columns = ui::grid_columns(size.width, app.max_columns);
+            let main_area_height = size.height.saturating_sub(1); // minus footer
+            app.adjust_scroll(main_area_height, ui::card_height(app.show_stats));
+
+            terminal.draw(|frame| ui::render(frame, app))?;
+            needs_redraw = false;
+        }
+
+        // Wake up regularly so changes on disk and the date rolling over show up without a key press
+        if event::poll(TICK_RATE)? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    app.status = None;
+                    handle_key(app, key);
+                    needs_redraw = true;
                 }
+                Event::Resize(..) => needs_redraw = true,
+                _ => {}
             }
         }
 
+        // Write whatever the key press changed, first folding in anything another
+        // process (e.g. `heat done` from a script) wrote since we last read the file
+        let mut changes = app.take_changes();
+        if !changes.is_empty() {
+            if store.changed_externally() {
+                let (merged, applied) = storage::merge(store.load()?, &app.data, &changes);
+                changes = applied;
+                app.replace_data(merged);
+                app.status = Some("Merged changes made outside this session".to_string());
+                needs_redraw = true;
+            }
+            store.apply(&app.data, &changes)?;
+        }
+
+        if watcher.as_ref().is_some_and(|w| w.has_changed()) && store.changed_externally() {
+            app.replace_data(store.load()?);
+            app.status = Some("Reloaded changes made outside this session".to_string());
+            needs_redraw = true;
+        }
+
+        // Past midnight, so "today", streaks and the heatmap all move on a day
+        if app.tick() {
+            needs_redraw = true;
+        }
+
         if app.should_quit {
-            storage::save_data(&app.data)?;
             return Ok(());
         }
     }
 }
+
+fn handle_key(app: &mut App, key: KeyEvent) {
+    match app.input_mode {
+        InputMode::Normal => match key.code {
+            KeyCode::Char('q') => app.quit(),
+            KeyCode::Char('j') => app.select_down(),
+            KeyCode::Char('k') => app.select_up(),
+            KeyCode::Char('h') => app.select_left(),
+            KeyCode::Char('l') => app.select_right(),
+            KeyCode::Char('g') => app.select_first(),
+            KeyCode::Char('G') => app.select_last(),
+            KeyCode::Char('a') => app.start_adding(),
+            KeyCode::Char('u') => app.undo(),
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
+            KeyCode::Char('r') => app.start_renaming(),
+            KeyCode::Char('f') => app.start_scheduling(),
+            KeyCode::Char('D') => app.archive_selected(),
+            KeyCode::Char('A') => app.start_archive(),
+            KeyCode::Char('<') => app.move_selected(-1),
+            KeyCode::Char('>') => app.move_selected(1),
+            KeyCode::Char('s') => app.toggle_stats(),
+            KeyCode::Char('t') => app.start_setting_target(),
+            KeyCode::Char('e') => app.start_cursor(),
+            KeyCode::Char('v') => app.start_detail(),
+            KeyCode::Char('c') => app.start_calendar(),
+            KeyCode::Char('x') => app.toggle_skip(),
+            KeyCode::Char('n') => app.start_editing_note(),
+            KeyCode::Char('?') => app.show_help(),
+            KeyCode::Enter => {
+                if app.selected_habit().is_some_and(|h| h.is_measured()) {
+                    app.start_entering_amount();
+                } else {
+                    app.toggle_today();
+                }
+            }
+            _ => {}
+        },
+        InputMode::Adding
+        | InputMode::Renaming
+        | InputMode::Scheduling
+        | InputMode::SettingTarget
+        | InputMode::EnteringAmount
+        | InputMode::EditingNote => match key.code {
+            KeyCode::Enter => app.confirm_input(),
+            KeyCode::Esc => app.cancel_input(),
+            KeyCode::Backspace => {
+                app.input_buffer.pop();
+            }
+            KeyCode::Char(c) => {
+                app.input_buffer.push(c);
+            }
+            _ => {}
+        },
+        InputMode::Cursor => match key.code {
+            KeyCode::Char('h') | KeyCode::Left => app.move_cursor(-7),
+            KeyCode::Char('l') | KeyCode::Right => app.move_cursor(7),
+            KeyCode::Char('k') | KeyCode::Up => app.move_cursor(-1),
+            KeyCode::Char('j') | KeyCode::Down => app.move_cursor(1),
+            KeyCode::Enter => {
+                if app.selected_habit().is_some_and(|h| h.is_measured()) {
+                    app.start_entering_amount();
+                } else {
+                    app.toggle_at_cursor();
+                }
+            }
+            KeyCode::Char('x') => app.toggle_skip(),
+            KeyCode::Char('n') => app.start_editing_note(),
+            KeyCode::Char('u') => app.undo(),
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
+            KeyCode::Esc | KeyCode::Char('e') | KeyCode::Char('q') => app.exit_cursor(),
+            _ => {}
+        },
+        InputMode::Detail => match key.code {
+            KeyCode::Char('h') | KeyCode::Left => app.page_detail(-1),
+            KeyCode::Char('l') | KeyCode::Right => app.page_detail(1),
+            KeyCode::Char('j') | KeyCode::Down => app.move_detail_note(1),
+            KeyCode::Char('k') | KeyCode::Up => app.move_detail_note(-1),
+            KeyCode::Esc | KeyCode::Char('v') | KeyCode::Char('q') => app.exit_detail(),
+            _ => {}
+        },
+        InputMode::Calendar => match key.code {
+            KeyCode::Char('h') | KeyCode::Left => app.move_calendar(-1, 0),
+            KeyCode::Char('l') | KeyCode::Right => app.move_calendar(1, 0),
+            KeyCode::Char('k') | KeyCode::Up => app.move_calendar(-7, 0),
+            KeyCode::Char('j') | KeyCode::Down => app.move_calendar(7, 0),
+            KeyCode::Char('H') | KeyCode::PageUp => app.move_calendar(0, -1),
+            KeyCode::Char('L') | KeyCode::PageDown => app.move_calendar(0, 1),
+            KeyCode::Tab => app.toggle_calendar_all(),
+            KeyCode::Enter => app.confirm_calendar_day(),
+            KeyCode::Char('x') => app.toggle_skip(),
+            KeyCode::Char('n') => app.start_editing_note(),
+            KeyCode::Char('u') => app.undo(),
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
+            KeyCode::Esc | KeyCode::Char('c') | KeyCode::Char('q') => app.exit_cursor(),
+            _ => {}
+        },
+        InputMode::Archive => match key.code {
+            KeyCode::Char('j') | KeyCode::Down => app.move_archive(1),
+            KeyCode::Char('k') | KeyCode::Up => app.move_archive(-1),
+            KeyCode::Enter => app.restore_archived(),
+            KeyCode::Char('D') => app.start_deleting(),
+            KeyCode::Char('u') => app.undo(),
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => app.redo(),
+            KeyCode::Esc | KeyCode::Char('A') | KeyCode::Char('q') => app.exit_archive(),
+            _ => {}
+        },
+        InputMode::Help => match key.code {
+            KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q') => app.cancel_input(),
+            _ => {}
+        },
+        InputMode::Deleting => match key.code {
+            KeyCode::Char('y') => app.confirm_delete(),
+            KeyCode::Char('n') | KeyCode::Esc => app.cancel_delete(),
+            _ => {}
+        },
+    }
+}
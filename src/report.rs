@@ -0,0 +1,153 @@
+//! Machine-readable output for `heat list --json` and `heat stats --json`.
+//!
+//! This is a public format for scripts and dashboards and is kept separate from the
+//! on-disk `AppData` layout, so storage changes don't break consumers. Fields are only
+//! ever added; removing or changing one bumps `schema_version`.
+//!
+//! Schema version 1:
+//!
+//! For habits to quit, streaks are clean runs in days and `completion_percentage` is the
+//! share of clean days.
+//!
+//! ```text
+//! {
+//!   "schema_version": 1,
+//!   "date": "2026-10-17",                // today's date when the report was made
+//!   "habits": [
+//!     {
+//!       "id": "uuid",
+//!       "name": "Water",
+//!       "created_at": "2026-01-01",
+//!       "kind": "measure",               // "check", "measure" or "quit"
+//!       "archived_on": null,             // date the habit was archived, if it is
+//!       "schedule": "daily",             // same syntax as `heat add --schedule`
+//!       "streak_unit": "day",            // "day", "week" or "month"
+//!       "current_streak": 4,
+//!       "longest_streak": 12,
+//!       "completion_percentage": 87,
+//!       "total_completions": 120,        // slips for habits to quit
+//!       "relapses_per_month": null,      // habits to quit only, e.g. { "2026-09": 2 }
+//!       "today": {
+//!         "due": true,                   // false on days the schedule skips
+//!         "done": false,                 // for habits to quit, true unless there was a slip
+//!         "excused": false,              // skipped, or on vacation
+//!         "amount": 3.0,                 // measured habits only, otherwise null
+//!         "target": 8.0,                 // measured habits only, otherwise null
+//!         "unit": "glasses"              // measured habits only, otherwise null
+//!       }
+//!     }
+//!   ]
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::data::{Habit, HabitKind, Vacation};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub schema_version: u32,
+    pub date: NaiveDate,
+    pub habits: Vec<HabitReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HabitReport {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: NaiveDate,
+    pub kind: &'static str,
+    pub archived_on: Option<NaiveDate>,
+    pub schedule: String,
+    pub streak_unit: &'static str,
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub completion_percentage: u32,
+    pub total_completions: usize,
+    pub relapses_per_month: Option<BTreeMap<String, u32>>,
+    pub today: DayStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayStatus {
+    pub due: bool,
+    pub done: bool,
+    pub excused: bool,
+    pub amount: Option<f64>,
+    pub target: Option<f64>,
+    pub unit: Option<String>,
+}
+
+impl Report {
+    pub fn new<'a>(
+        habits: impl IntoIterator<Item = &'a Habit>,
+        clock: &dyn Clock,
+        away: &[Vacation],
+    ) -> Self {
+        let today = clock.today();
+        Self {
+            schema_version: SCHEMA_VERSION,
+            date: today,
+            habits: habits
+                .into_iter()
+                .map(|habit| HabitReport::new(habit, clock, away))
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl HabitReport {
+    fn new(habit: &Habit, clock: &dyn Clock, away: &[Vacation]) -> Self {
+        let today = clock.today();
+        let (amount, target, unit) = match &habit.kind {
+            HabitKind::Check | HabitKind::Quit => (None, None, None),
+            HabitKind::Measure { target, unit } => {
+                (Some(habit.amount_on(today)), Some(*target), Some(unit.clone()))
+            }
+        };
+
+        Self {
+            id: habit.id,
+            name: habit.name.clone(),
+            created_at: habit.created_at,
+            kind: match habit.kind {
+                HabitKind::Check => "check",
+                HabitKind::Measure { .. } => "measure",
+                HabitKind::Quit => "quit",
+            },
+            archived_on: habit.archived_on,
+            schedule: habit.schedule.to_string(),
+            streak_unit: habit.streak_unit(),
+            current_streak: habit.current_streak(clock, away),
+            longest_streak: habit.longest_streak(clock, away),
+            completion_percentage: habit.completion_percentage(clock, away),
+            total_completions: habit.completions.len(),
+            relapses_per_month: habit.is_quit().then(|| {
+                habit
+                    .relapses_by_month()
+                    .into_iter()
+                    .map(|((year, month), count)| (format!("{}-{:02}", year, month), count))
+                    .collect()
+            }),
+            today: DayStatus {
+                due: habit.is_scheduled(today),
+                done: habit.progress_on(today) >= 1.0,
+                excused: habit.is_excused(today, away),
+                amount,
+                target,
+                unit,
+            },
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Backups kept when `HEAT_BACKUPS` isn't set
+const DEFAULT_BACKUP_COUNT: usize = 5;
+
+/// Automatic backups are skipped while the newest one is younger than this, so a script
+/// running heat many times in a row doesn't rotate out all the older backups
+const BACKUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Set once this process has backed up the data file, so a session only makes one backup
+static BACKED_UP: AtomicBool = AtomicBool::new(false);
+
+/// Directory holding timestamped copies of the data file at `path`
+pub fn get_backup_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .map(|parent| parent.join("backups"))
+        .unwrap_or_else(|| PathBuf::from("backups"))
+}
+
+/// Number of rotating backups to keep, from `HEAT_BACKUPS` (0 disables backups)
+pub fn backup_count() -> usize {
+    env::var("HEAT_BACKUPS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_BACKUP_COUNT)
+}
+
+/// Back up the data file before this session's first write to it, unless it was backed up
+/// within the last `BACKUP_INTERVAL`
+pub(super) fn backup_once(path: &Path) -> io::Result<()> {
+    if path.exists() && !BACKED_UP.swap(true, Ordering::SeqCst) && !has_recent_backup(path)? {
+        create_backup(path)?;
+    }
+    Ok(())
+}
+
+/// Whether the newest backup of the data file at `path` is younger than `BACKUP_INTERVAL`
+fn has_recent_backup(path: &Path) -> io::Result<bool> {
+    let Some(newest) = list_backups(path)?.into_iter().next() else {
+        return Ok(false);
+    };
+    // A modification time in the future (e.g. after the clock moved back) counts as recent
+    let age = fs::metadata(newest)?.modified()?.elapsed().unwrap_or_default();
+    Ok(age < BACKUP_INTERVAL)
+}
+
+/// Treat this session as backed up, e.g. after an explicit backup
+pub(super) fn mark_backed_up() {
+    BACKED_UP.store(true, Ordering::SeqCst);
+}
+
+/// Copy the data file into the backup directory and drop the oldest backups
+/// beyond `backup_count()`. Returns the new backup's path, if one was made.
+pub fn create_backup(path: &Path) -> io::Result<Option<PathBuf>> {
+    let keep = backup_count();
+    if keep == 0 || !path.exists() {
+        return Ok(None);
+    }
+
+    let backup_dir = get_backup_dir(path);
+    fs::create_dir_all(&backup_dir)?;
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    // Down to the millisecond, waiting for the next one if a backup already has this name
+    let backup_path = loop {
+        let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S-%3f");
+        let backup_path = backup_dir.join(format!("{}-{}.{}", stem, timestamp, extension));
+        if !backup_path.exists() {
+            break backup_path;
+        }
+        thread::sleep(Duration::from_millis(1));
+    };
+    fs::copy(path, &backup_path)?;
+
+    for old in list_backups(path)?.into_iter().skip(keep) {
+        fs::remove_file(old)?;
+    }
+
+    Ok(Some(backup_path))
+}
+
+/// Backups of the data file at `path`, newest first
+pub fn list_backups(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let backup_dir = get_backup_dir(path);
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let prefix = format!("{}-", path.file_stem().unwrap_or_default().to_string_lossy());
+    let suffix = format!(".{}", path.extension().unwrap_or_default().to_string_lossy());
+
+    let mut backups: Vec<PathBuf> = fs::read_dir(&backup_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with(&prefix) && name.ends_with(&suffix)
+        })
+        .collect();
+
+    // Timestamps sort lexicographically, so reverse name order is newest first
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
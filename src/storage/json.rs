@@ -0,0 +1,259 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::{backup, modified_time, Change, Storage};
+use crate::data::{AppData, Habit, Vacation, SCHEMA_VERSION};
+
+/// All habits in one pretty-printed JSON document. Every change rewrites the whole file,
+/// so the last loaded or saved state is kept to apply single-habit changes to.
+pub struct JsonStorage {
+    path: PathBuf,
+    data: AppData,
+    /// Modification time as of the last load or save, to spot writes by other processes
+    modified: Option<SystemTime>,
+}
+
+impl JsonStorage {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Self {
+            path,
+            data: AppData::new(),
+            modified: None,
+        })
+    }
+
+    fn write(&mut self) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(&self.data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        // Keep a copy of what was on disk before this session's first write
+        backup::backup_once(&self.path)?;
+
+        write_atomic(&self.path, contents.as_bytes())?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+
+    fn habit_mut(&mut self, id: Uuid) -> Option<&mut Habit> {
+        self.data.habits.iter_mut().find(|h| h.id == id)
+    }
+}
+
+impl Storage for JsonStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> io::Result<AppData> {
+        if !self.path.exists() {
+            self.data = AppData::new();
+            self.write()?;
+            return Ok(self.data.clone());
+        }
+
+        // Taken before reading, so a write landing in between is still noticed later
+        self.modified = modified_time(&self.path);
+        let (data, original_version) = read_file(&self.path)?;
+        self.data = data;
+
+        // Keep the file as it was before upgrading it, outside the rotating backups
+        if original_version < SCHEMA_VERSION {
+            let backup_dir = backup::get_backup_dir(&self.path);
+            fs::create_dir_all(&backup_dir)?;
+            let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+            fs::copy(&self.path, backup_dir.join(format!("{}.v{}.json", stem, original_version)))?;
+            self.write()?;
+        }
+
+        Ok(self.data.clone())
+    }
+
+    fn changed_externally(&self) -> bool {
+        modified_time(&self.path) != self.modified
+    }
+
+    fn save_all(&mut self, data: &AppData) -> io::Result<()> {
+        self.data = data.clone();
+        self.write()
+    }
+
+    fn save_habit(&mut self, habit: &Habit, position: usize) -> io::Result<()> {
+        self.data.habits.retain(|h| h.id != habit.id);
+        let position = position.min(self.data.habits.len());
+        self.data.habits.insert(position, habit.clone());
+        self.write()
+    }
+
+    fn delete_habit(&mut self, id: Uuid) -> io::Result<()> {
+        self.data.habits.retain(|h| h.id != id);
+        self.write()
+    }
+
+    fn record_completion(&mut self, id: Uuid, date: NaiveDate) -> io::Result<()> {
+        if let Some(habit) = self.habit_mut(id)
+            && !habit.completions.contains(&date)
+        {
+            habit.completions.push(date);
+        }
+        self.write()
+    }
+
+    fn remove_completion(&mut self, id: Uuid, date: NaiveDate) -> io::Result<()> {
+        if let Some(habit) = self.habit_mut(id) {
+            habit.completions.retain(|&d| d != date);
+        }
+        self.write()
+    }
+
+    fn save_vacations(&mut self, vacations: &[Vacation]) -> io::Result<()> {
+        self.data.vacations = vacations.to_vec();
+        self.write()
+    }
+
+    fn save_order(&mut self, order: &[Uuid]) -> io::Result<()> {
+        self.data.reorder(order);
+        self.write()
+    }
+
+    /// The whole file is rewritten anyway, so write the batch once
+    fn apply(&mut self, data: &AppData, changes: &[Change]) -> io::Result<()> {
+        if changes.is_empty() {
+            return Ok(());
+        }
+        self.save_all(data)
+    }
+}
+
+/// Read a stored document, upgrading it to the current schema in memory.
+/// Returns the data along with the schema version it was stored with.
+pub(super) fn read_file(path: &Path) -> io::Result<(AppData, u32)> {
+    let contents = fs::read_to_string(path)?;
+    parse_data(&contents)
+}
+
+fn parse_data(contents: &str) -> io::Result<(AppData, u32)> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+
+    let mut document: Value = serde_json::from_str(contents).map_err(|e| invalid(e.to_string()))?;
+
+    // Files written before versioning have no "version" field
+    let original_version = match document.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| invalid(format!("invalid schema version {}", v)))?,
+    };
+
+    if original_version > SCHEMA_VERSION {
+        return Err(invalid(format!(
+            "data file uses schema version {}, but this heat only understands up to {}; please upgrade heat",
+            original_version, SCHEMA_VERSION
+        )));
+    }
+
+    for version in original_version..SCHEMA_VERSION {
+        MIGRATIONS[version as usize](&mut document)
+            .map_err(|e| invalid(format!("migrating from schema version {}: {}", version, e)))?;
+        document["version"] = Value::from(version + 1);
+    }
+
+    let data = serde_json::from_value(document).map_err(|e| invalid(e.to_string()))?;
+    Ok((data, original_version))
+}
+
+/// Upgrades a raw document by one schema version
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Step-by-step upgrades of the raw document; entry `n` upgrades version `n` to `n + 1`
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [migrate_v0_to_v1, migrate_v1_to_v2];
+
+/// Version 0 is the unversioned format with only id, name, created_at and completions per habit
+fn migrate_v0_to_v1(document: &mut Value) -> Result<(), String> {
+    let habits = document
+        .get_mut("habits")
+        .and_then(Value::as_array_mut)
+        .ok_or("missing \"habits\" list")?;
+
+    for habit in habits {
+        let habit = habit.as_object_mut().ok_or("habit is not an object")?;
+        habit.entry("schedule").or_insert_with(|| json!({ "type": "daily" }));
+        habit.entry("kind").or_insert_with(|| json!({ "type": "check" }));
+        habit.entry("amounts").or_insert_with(|| json!({}));
+    }
+
+    Ok(())
+}
+
+/// Version 2 only adds fields that default to empty, so there's nothing to convert; the
+/// bump keeps older builds, which would drop them, from writing the file
+fn migrate_v1_to_v2(_document: &mut Value) -> Result<(), String> {
+    Ok(())
+}
+
+/// Write to a temporary file next to `path`, sync it, then rename it over `path`
+/// so a crash mid-write never leaves a truncated file behind
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)?;
+
+    // Persist the rename itself; directories can't be opened for syncing on every platform
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        File::open(parent)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{HabitKind, Schedule};
+
+    #[test]
+    fn reads_unversioned_v0_document() {
+        let contents = r#"{
+            "habits": [{
+                "id": "5f0c6a4e-8d1b-4c3e-9f3a-2b7d1e6c9a10",
+                "name": "Read",
+                "created_at": "2026-01-01",
+                "completions": ["2026-01-01", "2026-01-02"]
+            }]
+        }"#;
+        let (data, original_version) = parse_data(contents).unwrap();
+        assert_eq!(original_version, 0);
+        assert_eq!(data.version, SCHEMA_VERSION);
+
+        let habit = &data.habits[0];
+        assert_eq!(habit.name, "Read");
+        assert_eq!(habit.completions.len(), 2);
+        assert!(matches!(habit.schedule, Schedule::Daily));
+        assert!(matches!(habit.kind, HabitKind::Check));
+    }
+
+    #[test]
+    fn rejects_newer_schema_version() {
+        let contents = format!(r#"{{ "version": {}, "habits": [] }}"#, SCHEMA_VERSION + 1);
+        let error = parse_data(&contents).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("please upgrade heat"));
+    }
+}
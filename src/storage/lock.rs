@@ -0,0 +1,28 @@
+use std::fs::{File, TryLockError};
+use std::io;
+use std::path::Path;
+
+/// Advisory lock marking a data file as open in an interactive session.
+/// Released when dropped, or by the OS if the process dies.
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// Try to take the session lock for the data file at `path`.
+/// Returns `None` if another instance already holds it.
+pub fn try_lock(path: &Path) -> io::Result<Option<InstanceLock>> {
+    let mut lock_name = path.file_name().unwrap_or_default().to_os_string();
+    lock_name.push(".lock");
+
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_file_name(lock_name))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Some(InstanceLock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
@@ -0,0 +1,284 @@
+mod backup;
+mod json;
+mod lock;
+mod sqlite;
+mod watch;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::data::{AppData, Habit, Vacation};
+
+pub use backup::{get_backup_dir, list_backups};
+pub use json::JsonStorage;
+pub use lock::try_lock;
+pub use sqlite::SqliteStorage;
+pub use watch::FileWatcher;
+
+/// Which file format habits are stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// A single pretty-printed JSON document, rewritten on every change
+    #[default]
+    Json,
+    /// An embedded SQLite database, updated one row at a time
+    Sqlite,
+}
+
+impl Backend {
+    fn file_name(self) -> &'static str {
+        match self {
+            Backend::Json => "data.json",
+            Backend::Sqlite => "data.db",
+        }
+    }
+}
+
+pub fn get_data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("heat")
+}
+
+pub fn get_data_path(backend: Backend) -> PathBuf {
+    get_data_dir().join(backend.file_name())
+}
+
+/// Data file for a named profile, kept apart from the default one under `profiles/`
+pub fn get_profile_path(profile: &str, backend: Backend) -> PathBuf {
+    let extension = Path::new(backend.file_name()).extension().unwrap_or_default();
+    get_data_dir()
+        .join("profiles")
+        .join(profile)
+        .with_extension(extension)
+}
+
+/// A change to `AppData` that still needs to be written to storage
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// A check habit's day was marked done or not done
+    Completion { habit: Uuid, date: NaiveDate, done: bool },
+    /// A habit was added, or anything other than a single completion changed
+    Habit(Uuid),
+    /// A habit was deleted
+    Removed(Uuid),
+    /// The vacations from every habit changed
+    Vacations,
+    /// Habits were moved around
+    Order,
+}
+
+impl Change {
+    /// The change for a habit's `date` after it was marked or cleared. Check habits only
+    /// flip one day; measured ones also change the recorded amount.
+    pub fn for_day(habit: &Habit, date: NaiveDate) -> Self {
+        if habit.is_measured() {
+            Change::Habit(habit.id)
+        } else {
+            Change::Completion {
+                habit: habit.id,
+                date,
+                done: habit.completions.contains(&date),
+            }
+        }
+    }
+}
+
+/// Persistence for habits. Implementations apply individual changes as cheaply as
+/// their format allows, so callers should prefer the narrowest method.
+pub trait Storage {
+    /// File the habits are stored in
+    fn path(&self) -> &Path;
+
+    /// Read every habit
+    fn load(&mut self) -> io::Result<AppData>;
+
+    /// Whether another process has written the file since it was last loaded or saved here
+    fn changed_externally(&self) -> bool;
+
+    /// Replace everything stored with `data`
+    fn save_all(&mut self, data: &AppData) -> io::Result<()>;
+
+    /// Insert or update a habit, including its history, at `position` in the habit order
+    fn save_habit(&mut self, habit: &Habit, position: usize) -> io::Result<()>;
+
+    /// Delete a habit and its history
+    fn delete_habit(&mut self, id: Uuid) -> io::Result<()>;
+
+    /// Mark `date` as done for a habit
+    fn record_completion(&mut self, id: Uuid, date: NaiveDate) -> io::Result<()>;
+
+    /// Mark `date` as not done for a habit
+    fn remove_completion(&mut self, id: Uuid, date: NaiveDate) -> io::Result<()>;
+
+    /// Replace the vacations from every habit
+    fn save_vacations(&mut self, vacations: &[Vacation]) -> io::Result<()>;
+
+    /// Put the stored habits in the order of `order`
+    fn save_order(&mut self, order: &[Uuid]) -> io::Result<()>;
+
+    /// Write a batch of changes, reading the current state of changed habits from `data`
+    fn apply(&mut self, data: &AppData, changes: &[Change]) -> io::Result<()> {
+        for change in changes {
+            match *change {
+                Change::Completion { habit, date, done: true } => self.record_completion(habit, date)?,
+                Change::Completion { habit, date, done: false } => self.remove_completion(habit, date)?,
+                Change::Habit(id) => {
+                    if let Some(position) = data.habits.iter().position(|h| h.id == id) {
+                        self.save_habit(&data.habits[position], position)?;
+                    }
+                }
+                Change::Removed(id) => self.delete_habit(id)?,
+                Change::Vacations => self.save_vacations(&data.vacations)?,
+                Change::Order => self.save_order(&data.order())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Last modification time of `path`, or `None` if it can't be read
+pub(crate) fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Re-apply `changes`, taken from `ours`, on top of a fresher copy of the data read from disk.
+/// Changes made elsewhere survive unless they touch the same habit as one of ours.
+/// Returns the merged data and the changes that still apply to it, leaving out those
+/// for habits deleted elsewhere.
+pub fn merge(mut disk: AppData, ours: &AppData, changes: &[Change]) -> (AppData, Vec<Change>) {
+    let mut applied = Vec::new();
+    for change in changes {
+        match *change {
+            Change::Completion { habit, date, done } => {
+                let Some(habit) = disk.habits.iter_mut().find(|h| h.id == habit) else {
+                    continue;
+                };
+                habit.completions.retain(|&d| d != date);
+                if done {
+                    habit.completions.push(date);
+                }
+            }
+            Change::Habit(id) => {
+                let Some(position) = ours.habits.iter().position(|h| h.id == id) else {
+                    continue;
+                };
+                let habit = ours.habits[position].clone();
+                match disk.habits.iter().position(|h| h.id == id) {
+                    Some(existing) => disk.habits[existing] = habit,
+                    None => {
+                        let position = position.min(disk.habits.len());
+                        disk.habits.insert(position, habit);
+                    }
+                }
+            }
+            Change::Removed(id) => disk.habits.retain(|h| h.id != id),
+            Change::Vacations => disk.vacations = ours.vacations.clone(),
+            Change::Order => disk.reorder(&ours.order()),
+        }
+        applied.push(change.clone());
+    }
+    (disk, applied)
+}
+
+/// Open the data file at `path` for `backend`, or the default one if `None`
+pub fn open(backend: Backend, path: Option<PathBuf>) -> io::Result<Box<dyn Storage>> {
+    let default_location = path.is_none();
+    // A bare file name has an empty parent, which backups, syncing and the watcher all need
+    let path = std::path::absolute(path.unwrap_or_else(|| get_data_path(backend)))?;
+    Ok(match backend {
+        Backend::Json => Box::new(JsonStorage::open(path)?),
+        Backend::Sqlite => {
+            // Bring existing habits along the first time the default SQLite file is used,
+            // reading them before the database is created so a bad file doesn't leave it empty
+            let json_path = get_data_path(Backend::Json);
+            let import = if default_location && !path.exists() && json_path.exists() {
+                Some(json::read_file(&json_path)?.0)
+            } else {
+                None
+            };
+
+            let mut store = SqliteStorage::open(path)?;
+            if let Some(data) = import {
+                store.save_all(&data)?;
+            }
+            Box::new(store)
+        }
+    })
+}
+
+/// Replace the stored habits with those in `backup`, after backing up the current file.
+/// The backup may be in either format, so this also converts between backends.
+pub fn restore_backup(store: &mut dyn Storage, backup: &Path) -> io::Result<AppData> {
+    let data = if backup.extension().is_some_and(|ext| ext == "db") {
+        SqliteStorage::open_read_only(backup)?.load()?
+    } else {
+        json::read_file(backup)?.0
+    };
+
+    backup::create_backup(store.path())?;
+    backup::mark_backed_up();
+    store.save_all(&data)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{data_with, date, habit_on};
+
+    #[test]
+    fn merge_keeps_changes_made_outside_the_session() {
+        let mut ours = data_with(&["Read"]);
+        let mut disk = ours.clone();
+        let read = ours.habits[0].id;
+
+        // Elsewhere: Read was done on the 2nd and Run was added
+        disk.habits[0].completions.push(date(2026, 1, 2));
+        disk.habits.push(habit_on("Run", date(2026, 1, 1)));
+
+        // Here: Read was done on the 3rd and Sleep was added
+        ours.habits[0].completions.push(date(2026, 1, 3));
+        ours.habits.push(habit_on("Sleep", date(2026, 1, 1)));
+        let changes = [
+            Change::Completion {
+                habit: read,
+                date: date(2026, 1, 3),
+                done: true,
+            },
+            Change::Habit(ours.habits[1].id),
+        ];
+
+        let (merged, applied) = merge(disk, &ours, &changes);
+        assert_eq!(applied, changes);
+        let names: Vec<&str> = merged.habits.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, ["Read", "Sleep", "Run"]);
+        let mut completions = merged.habits[0].completions.clone();
+        completions.sort();
+        assert_eq!(completions, [date(2026, 1, 2), date(2026, 1, 3)]);
+    }
+
+    #[test]
+    fn merge_drops_changes_to_habits_deleted_elsewhere() {
+        let mut ours = data_with(&["Read"]);
+        ours.habits[0].completions.push(date(2026, 1, 1));
+        let changes = [
+            Change::Completion {
+                habit: ours.habits[0].id,
+                date: date(2026, 1, 1),
+                done: true,
+            },
+            Change::Vacations,
+        ];
+
+        let (merged, applied) = merge(AppData::new(), &ours, &changes);
+        assert!(merged.habits.is_empty());
+        assert_eq!(applied, [Change::Vacations]);
+    }
+}
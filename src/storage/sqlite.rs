@@ -0,0 +1,392 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use uuid::Uuid;
+
+use super::{backup, modified_time, Storage};
+use crate::data::{AppData, Habit, Vacation, SCHEMA_VERSION};
+
+/// Version of the table layout below, tracked in SQLite's `user_version`
+const TABLES_VERSION: i32 = 3;
+
+/// Habits keep their details (name, schedule, kind, ...) as a JSON document so new
+/// `Habit` fields don't need table changes; per-day history gets a row per habit and date.
+/// Version 2 added the vacations table, for time off from every habit. Version 3 changed no
+/// tables, but the details gained habits to quit, skips, notes and archiving, which older
+/// builds can't read or would drop.
+const CREATE_TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS habits (
+        id TEXT PRIMARY KEY,
+        position INTEGER NOT NULL,
+        details TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS days (
+        habit_id TEXT NOT NULL REFERENCES habits(id) ON DELETE CASCADE,
+        date TEXT NOT NULL,
+        completed INTEGER NOT NULL,
+        amount REAL,
+        PRIMARY KEY (habit_id, date)
+    );
+    CREATE TABLE IF NOT EXISTS vacations (
+        start_date TEXT NOT NULL,
+        end_date TEXT NOT NULL
+    );
+";
+
+/// Habits in an embedded SQLite database, updated a row at a time
+pub struct SqliteStorage {
+    path: PathBuf,
+    conn: Connection,
+    /// Modification time as of the last load or write, to spot writes by other processes
+    modified: Option<SystemTime>,
+}
+
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+fn json_error(e: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+impl SqliteStorage {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(&path).map_err(sql_error)?;
+        conn.pragma_update(None, "foreign_keys", true)
+            .map_err(sql_error)?;
+
+        let version: i32 = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(sql_error)?;
+        if version > TABLES_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "database uses table version {}, but this heat only understands up to {}; please upgrade heat",
+                    version, TABLES_VERSION
+                ),
+            ));
+        }
+
+        conn.execute_batch(CREATE_TABLES).map_err(sql_error)?;
+        conn.pragma_update(None, "user_version", TABLES_VERSION)
+            .map_err(sql_error)?;
+
+        Ok(Self {
+            path,
+            conn,
+            modified: None,
+        })
+    }
+
+    /// Open an existing database without creating or changing anything, e.g. a backup
+    pub fn open_read_only(path: &Path) -> io::Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(sql_error)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            conn,
+            modified: None,
+        })
+    }
+
+    /// Rewrite a habit's row and day rows within the current transaction
+    fn write_habit(conn: &Connection, habit: &Habit, position: usize) -> io::Result<()> {
+        // History lives in the days table, so leave it out of the details document
+        let details = Habit {
+            completions: Vec::new(),
+            amounts: BTreeMap::new(),
+            ..habit.clone()
+        };
+        let details = serde_json::to_string(&details).map_err(json_error)?;
+        let id = habit.id.to_string();
+
+        conn.execute(
+            "INSERT INTO habits (id, position, details) VALUES (?1, ?2, ?3)
+             ON CONFLICT(id) DO UPDATE SET position = excluded.position, details = excluded.details",
+            params![id, position as i64, details],
+        )
+        .map_err(sql_error)?;
+
+        conn.execute("DELETE FROM days WHERE habit_id = ?1", params![id])
+            .map_err(sql_error)?;
+
+        let mut dates: Vec<NaiveDate> = habit.completions.clone();
+        dates.extend(habit.amounts.keys().copied());
+        dates.sort();
+        dates.dedup();
+
+        let mut insert = conn
+            .prepare_cached("INSERT INTO days (habit_id, date, completed, amount) VALUES (?1, ?2, ?3, ?4)")
+            .map_err(sql_error)?;
+        for date in dates {
+            insert
+                .execute(params![
+                    id,
+                    date,
+                    habit.completions.contains(&date),
+                    habit.amounts.get(&date)
+                ])
+                .map_err(sql_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Rewrite the vacations table within the current transaction
+    fn write_vacations(conn: &Connection, vacations: &[Vacation]) -> io::Result<()> {
+        conn.execute("DELETE FROM vacations", []).map_err(sql_error)?;
+        let mut insert = conn
+            .prepare_cached("INSERT INTO vacations (start_date, end_date) VALUES (?1, ?2)")
+            .map_err(sql_error)?;
+        for vacation in vacations {
+            insert
+                .execute(params![vacation.start, vacation.end])
+                .map_err(sql_error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> io::Result<AppData> {
+        self.modified = modified_time(&self.path);
+        let mut habits: Vec<Habit> = Vec::new();
+
+        let mut query = self
+            .conn
+            .prepare("SELECT details FROM habits ORDER BY position")
+            .map_err(sql_error)?;
+        let rows = query
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(sql_error)?;
+        for details in rows {
+            let details = details.map_err(sql_error)?;
+            habits.push(serde_json::from_str(&details).map_err(json_error)?);
+        }
+
+        let index: HashMap<String, usize> = habits
+            .iter()
+            .enumerate()
+            .map(|(i, habit)| (habit.id.to_string(), i))
+            .collect();
+        let mut query = self
+            .conn
+            .prepare("SELECT habit_id, date, completed, amount FROM days ORDER BY date")
+            .map_err(sql_error)?;
+        let rows = query
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, NaiveDate>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, Option<f64>>(3)?,
+                ))
+            })
+            .map_err(sql_error)?;
+        for row in rows {
+            let (habit_id, date, completed, amount) = row.map_err(sql_error)?;
+            let Some(&i) = index.get(&habit_id) else {
+                continue;
+            };
+            let habit = &mut habits[i];
+            if completed {
+                habit.completions.push(date);
+            }
+            if let Some(amount) = amount {
+                habit.amounts.insert(date, amount);
+            }
+        }
+
+        let mut query = self
+            .conn
+            .prepare("SELECT start_date, end_date FROM vacations ORDER BY start_date")
+            .map_err(sql_error)?;
+        let vacations = query
+            .query_map([], |row| {
+                Ok(Vacation {
+                    start: row.get(0)?,
+                    end: row.get(1)?,
+                })
+            })
+            .map_err(sql_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(sql_error)?;
+
+        Ok(AppData {
+            version: SCHEMA_VERSION,
+            habits,
+            vacations,
+        })
+    }
+
+    fn changed_externally(&self) -> bool {
+        modified_time(&self.path) != self.modified
+    }
+
+    fn save_all(&mut self, data: &AppData) -> io::Result<()> {
+        backup::backup_once(&self.path)?;
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        tx.execute("DELETE FROM habits", []).map_err(sql_error)?;
+        for (position, habit) in data.habits.iter().enumerate() {
+            Self::write_habit(&tx, habit, position)?;
+        }
+        Self::write_vacations(&tx, &data.vacations)?;
+        tx.commit().map_err(sql_error)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+
+    fn save_habit(&mut self, habit: &Habit, position: usize) -> io::Result<()> {
+        backup::backup_once(&self.path)?;
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        Self::write_habit(&tx, habit, position)?;
+        tx.commit().map_err(sql_error)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+
+    fn delete_habit(&mut self, id: Uuid) -> io::Result<()> {
+        backup::backup_once(&self.path)?;
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        let id = id.to_string();
+
+        let position: Option<i64> = tx
+            .query_row("SELECT position FROM habits WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()
+            .map_err(sql_error)?;
+        if let Some(position) = position {
+            tx.execute("DELETE FROM habits WHERE id = ?1", params![id])
+                .map_err(sql_error)?;
+            // Keep positions contiguous so a habit added later lands at the end
+            tx.execute(
+                "UPDATE habits SET position = position - 1 WHERE position > ?1",
+                params![position],
+            )
+            .map_err(sql_error)?;
+        }
+
+        tx.commit().map_err(sql_error)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+
+    fn record_completion(&mut self, id: Uuid, date: NaiveDate) -> io::Result<()> {
+        backup::backup_once(&self.path)?;
+        self.conn
+            .execute(
+                "INSERT INTO days (habit_id, date, completed) VALUES (?1, ?2, 1)
+                 ON CONFLICT(habit_id, date) DO UPDATE SET completed = 1",
+                params![id.to_string(), date],
+            )
+            .map_err(sql_error)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+
+    fn remove_completion(&mut self, id: Uuid, date: NaiveDate) -> io::Result<()> {
+        backup::backup_once(&self.path)?;
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        let id = id.to_string();
+        tx.execute(
+            "DELETE FROM days WHERE habit_id = ?1 AND date = ?2 AND amount IS NULL",
+            params![id, date],
+        )
+        .map_err(sql_error)?;
+        tx.execute(
+            "UPDATE days SET completed = 0 WHERE habit_id = ?1 AND date = ?2",
+            params![id, date],
+        )
+        .map_err(sql_error)?;
+        tx.commit().map_err(sql_error)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+
+    fn save_vacations(&mut self, vacations: &[Vacation]) -> io::Result<()> {
+        backup::backup_once(&self.path)?;
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        Self::write_vacations(&tx, vacations)?;
+        tx.commit().map_err(sql_error)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+
+    fn save_order(&mut self, order: &[Uuid]) -> io::Result<()> {
+        backup::backup_once(&self.path)?;
+        let tx = self.conn.transaction().map_err(sql_error)?;
+        {
+            let mut update = tx
+                .prepare_cached("UPDATE habits SET position = ?2 WHERE id = ?1")
+                .map_err(sql_error)?;
+            for (position, id) in order.iter().enumerate() {
+                update
+                    .execute(params![id.to_string(), position as i64])
+                    .map_err(sql_error)?;
+            }
+        }
+        tx.commit().map_err(sql_error)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{data_with, date};
+    use crate::data::HabitKind;
+
+    #[test]
+    fn save_all_and_load_round_trip() {
+        // A directory of its own, since the first save also writes a backup next to the file
+        let dir = std::env::temp_dir().join(format!("heat-test-{}", Uuid::new_v4()));
+
+        let mut data = data_with(&["Read", "Run", "Sleep"]);
+        data.habits[0].completions = vec![date(2026, 1, 1), date(2026, 1, 3)];
+        data.habits[0].toggle_skip(date(2026, 1, 2));
+        data.habits[1].kind = HabitKind::Measure {
+            target: 5.0,
+            unit: "km".to_string(),
+        };
+        data.habits[1].set_amount(date(2026, 1, 2), 2.5);
+        data.habits[2].vacations.push(Vacation {
+            start: date(2026, 1, 5),
+            end: date(2026, 1, 6),
+        });
+        data.vacations.push(Vacation {
+            start: date(2026, 2, 1),
+            end: date(2026, 2, 7),
+        });
+
+        let mut store = SqliteStorage::open(dir.join("data.db")).unwrap();
+        store.save_all(&data).unwrap();
+        // Reopen, so nothing can come from the connection's state
+        let loaded = SqliteStorage::open(dir.join("data.db")).unwrap().load();
+        fs::remove_dir_all(&dir).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.order(), data.order());
+        let read = &loaded.habits[0];
+        assert_eq!(read.completions, [date(2026, 1, 1), date(2026, 1, 3)]);
+        assert_eq!(read.skipped, [date(2026, 1, 2)]);
+        let run = &loaded.habits[1];
+        assert!(run.is_measured());
+        assert_eq!(run.amount_on(date(2026, 1, 2)), 2.5);
+        assert_eq!(loaded.habits[2].vacations, data.habits[2].vacations);
+        assert_eq!(loaded.vacations, data.vacations);
+    }
+}
@@ -0,0 +1,49 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Watches a data file for changes made by other processes
+pub struct FileWatcher {
+    _watcher: RecommendedWatcher,
+    events: Receiver<()>,
+}
+
+impl FileWatcher {
+    /// Start watching `path`. The parent directory is watched rather than the file itself,
+    /// since atomic saves replace the file and a watch on the old one would go quiet.
+    pub fn new(path: &Path) -> io::Result<Self> {
+        let target: PathBuf = path.to_path_buf();
+        let (tx, events) = mpsc::channel();
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if let Ok(event) = event
+                && event.paths.iter().any(|p| p == &target)
+            {
+                let _ = tx.send(());
+            }
+        })
+        .map_err(io::Error::other)?;
+
+        let dir = path.parent().unwrap_or(Path::new("."));
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(io::Error::other)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Whether the file was touched since the last call. This includes our own writes,
+    /// so check `Storage::changed_externally` before reloading.
+    pub fn has_changed(&self) -> bool {
+        let mut changed = false;
+        while self.events.try_recv().is_ok() {
+            changed = true;
+        }
+        changed
+    }
+}
@@ -0,0 +1,110 @@
+use std::str::FromStr;
+
+use ratatui::style::Color;
+
+/// Colors used across the TUI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Key hints, the selected card, popup borders and status messages
+    pub accent: Color,
+    /// Separators, hints and missed days
+    pub muted: Color,
+    /// Completed days
+    pub done: Color,
+    /// Skipped days and vacations
+    pub skipped: Color,
+    /// Errors and the read-only badge
+    pub error: Color,
+    /// Text being typed into a popup
+    pub input: Color,
+    /// Text drawn on top of an accent or error background
+    pub highlight_text: Color,
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        accent: Color::Yellow,
+        muted: Color::DarkGray,
+        done: Color::Green,
+        skipped: Color::Blue,
+        error: Color::Red,
+        input: Color::White,
+        highlight_text: Color::Black,
+    };
+
+    pub const LIGHT: Theme = Theme {
+        accent: Color::Blue,
+        muted: Color::DarkGray,
+        done: Color::Green,
+        skipped: Color::Cyan,
+        error: Color::Red,
+        input: Color::Black,
+        highlight_text: Color::White,
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        accent: Color::LightYellow,
+        muted: Color::White,
+        done: Color::LightGreen,
+        skipped: Color::LightCyan,
+        error: Color::LightRed,
+        input: Color::White,
+        highlight_text: Color::Black,
+    };
+
+    /// Okabe-Ito colors, which stay distinguishable with the common kinds of color blindness
+    pub const COLORBLIND_SAFE: Theme = Theme {
+        accent: Color::Rgb(230, 159, 0),
+        muted: Color::DarkGray,
+        done: Color::Rgb(86, 180, 233),
+        skipped: Color::Rgb(204, 121, 167),
+        error: Color::Rgb(213, 94, 0),
+        input: Color::White,
+        highlight_text: Color::Black,
+    };
+
+    pub const BUILT_IN: [(&'static str, Theme); 4] = [
+        ("dark", Self::DARK),
+        ("light", Self::LIGHT),
+        ("high-contrast", Self::HIGH_CONTRAST),
+        ("colorblind-safe", Self::COLORBLIND_SAFE),
+    ];
+
+    /// Look up a built-in theme by name
+    pub fn named(name: &str) -> Option<Theme> {
+        let name = name.to_lowercase();
+        Self::BUILT_IN
+            .iter()
+            .find(|(built_in, _)| *built_in == name)
+            .map(|(_, theme)| *theme)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+/// Parse a color name ("green", "dark gray"), a hex code ("#3fb950"),
+/// an "rgb(63, 185, 80)" triple or a 256-color palette index
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    let s = s.trim();
+    let invalid = || format!("invalid color \"{}\"", s);
+
+    if let Some(channels) = s
+        .strip_prefix("rgb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let channels: Vec<u8> = channels
+            .split(',')
+            .map(|c| c.trim().parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        return match channels[..] {
+            [r, g, b] => Ok(Color::Rgb(r, g, b)),
+            _ => Err(invalid()),
+        };
+    }
+
+    Color::from_str(s).map_err(|_| invalid())
+}
@@ -1,17 +1,20 @@
 use chrono::{Datelike, Duration, NaiveDate, Weekday};
 use ratatui::{
     layout::{Constraint, Layout, Rect},
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 
-use crate::app::{App, InputMode, GRID_COLUMNS};
-use crate::data::Habit;
+use crate::app::{App, InputMode};
+use crate::data::{format_amount, start_of_week, Habit, HabitKind, Schedule, Vacation};
+use crate::theme::Theme;
 
 const CARD_HEIGHT_WITH_STATS: u16 = 10;
 const CARD_HEIGHT_NO_STATS: u16 = 9;
+/// Narrowest a card gets before the grid drops a column
+const MIN_CARD_WIDTH: u16 = 36;
 
 pub fn card_height(show_stats: bool) -> u16 {
     if show_stats {
@@ -21,6 +24,12 @@ pub fn card_height(show_stats: bool) -> u16 {
     }
 }
 
+/// Number of card columns that fit in `width`, capped at `max_columns` if set
+pub fn grid_columns(width: u16, max_columns: Option<usize>) -> usize {
+    let fit = (width / MIN_CARD_WIDTH).max(1) as usize;
+    max_columns.map_or(fit, |max| fit.min(max))
+}
+
 /// Truncate a string to fit within max_width, adding "..." if truncated
 fn truncate_name(name: &str, max_width: usize) -> String {
     if name.chars().count() <= max_width {
@@ -34,6 +43,7 @@ fn truncate_name(name: &str, max_width: usize) -> String {
 }
 
 pub fn render(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
     let area = frame.area();
 
     let layout = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).split(area);
@@ -42,16 +52,58 @@ pub fn render(frame: &mut Frame, app: &App) {
     let footer_area = layout[1];
 
     // Main area
-    if app.data.habits.is_empty() {
-        let empty_state = render_empty_state();
+    if matches!(app.input_mode, InputMode::Archive | InputMode::Deleting) {
+        render_archive_view(frame, app, main_area);
+    } else if app.selected_habit().is_none() {
+        let empty_state = render_empty_state(&app.theme, app.archived_habits().len());
         frame.render_widget(empty_state, main_area);
+    } else if app.input_mode == InputMode::Detail {
+        render_detail_view(frame, app, main_area);
+    } else if app.in_calendar() {
+        render_calendar_view(frame, app, main_area);
     } else {
         render_habit_list(frame, app, main_area);
     }
 
     // Controls bar footer
-    let controls = render_controls_bar();
-    frame.render_widget(controls, footer_area);
+    let controls = if let Some(status) = &app.status {
+        render_status_bar(status, &app.theme)
+    } else if app.input_mode == InputMode::Cursor {
+        render_cursor_bar(app)
+    } else if app.input_mode == InputMode::Detail {
+        render_detail_bar(app)
+    } else if app.input_mode == InputMode::Calendar {
+        render_calendar_bar(app)
+    } else if matches!(app.input_mode, InputMode::Archive | InputMode::Deleting) {
+        render_archive_bar(&app.theme)
+    } else {
+        render_controls_bar(&app.theme)
+    };
+
+    // Keep the profile name and a read-only marker in the corner for the whole session
+    let mut badges = Vec::new();
+    if let Some(profile) = &app.profile {
+        badges.push(Span::styled(
+            format!(" {} ", profile),
+            Style::default().fg(theme.highlight_text).bg(theme.accent),
+        ));
+    }
+    if app.read_only {
+        badges.push(Span::styled(
+            " READ-ONLY ",
+            Style::default().fg(theme.highlight_text).bg(theme.error),
+        ));
+    }
+
+    if badges.is_empty() {
+        frame.render_widget(controls, footer_area);
+    } else {
+        let badge_width: usize = badges.iter().map(Span::width).sum();
+        let layout = Layout::horizontal([Constraint::Length(badge_width as u16), Constraint::Min(0)])
+            .split(footer_area);
+        frame.render_widget(Paragraph::new(Line::from(badges)), layout[0]);
+        frame.render_widget(controls, layout[1]);
+    }
 
     // Render popup if in adding, renaming, or deleting mode
     if app.input_mode == InputMode::Adding {
@@ -60,6 +112,16 @@ pub fn render(frame: &mut Frame, app: &App) {
         render_rename_popup(frame, app, area);
     } else if app.input_mode == InputMode::Deleting {
         render_delete_popup(frame, app, area);
+    } else if app.input_mode == InputMode::Scheduling {
+        render_schedule_popup(frame, app, area);
+    } else if app.input_mode == InputMode::SettingTarget {
+        render_target_popup(frame, app, area);
+    } else if app.input_mode == InputMode::EnteringAmount {
+        render_amount_popup(frame, app, area);
+    } else if app.input_mode == InputMode::EditingNote {
+        render_note_popup(frame, app, area);
+    } else if app.input_mode == InputMode::Help {
+        render_help_popup(frame, theme, area);
     }
 }
 
@@ -86,28 +148,32 @@ fn render_habit_list(frame: &mut Frame, app: &App, area: Rect) {
     let row_areas = Layout::vertical(row_constraints).split(area);
 
     // Create column constraints (equal width for each column)
-    let col_constraints: Vec<Constraint> = (0..GRID_COLUMNS)
-        .map(|_| Constraint::Ratio(1, GRID_COLUMNS as u32))
+    let col_constraints: Vec<Constraint> = (0..app.columns)
+        .map(|_| Constraint::Ratio(1, app.columns as u32))
         .collect();
 
+    let grid = app.grid_habits();
     for (row_offset, row_area) in row_areas.iter().enumerate() {
         let row = start_row + row_offset;
         let col_areas = Layout::horizontal(col_constraints.clone()).split(*row_area);
 
-        for col in 0..GRID_COLUMNS {
-            let habit_index = row * GRID_COLUMNS + col;
-            if habit_index < app.data.habits.len() {
+        for (col, col_area) in col_areas.iter().enumerate() {
+            if let Some(&habit_index) = grid.get(row * app.columns + col) {
                 let habit = &app.data.habits[habit_index];
                 let is_selected = habit_index == app.selected_index;
-                render_habit_card(frame, habit, col_areas[col], is_selected, app.show_stats);
+                render_habit_card(frame, app, habit, *col_area, is_selected);
             }
         }
     }
 }
 
-fn render_habit_card(frame: &mut Frame, habit: &Habit, area: Rect, is_selected: bool, show_stats: bool) {
+fn render_habit_card(frame: &mut Frame, app: &App, habit: &Habit, area: Rect, is_selected: bool) {
+    let theme = &app.theme;
+    let today = app.today;
+    let cursor = if is_selected { app.cursor_date } else { None };
+
     let border_style = if is_selected {
-        Style::default().fg(Color::Yellow)
+        Style::default().fg(theme.accent)
     } else {
         Style::default()
     };
@@ -116,15 +182,30 @@ fn render_habit_card(frame: &mut Frame, habit: &Habit, area: Rect, is_selected:
     let max_name_width = area.width.saturating_sub(6) as usize;
     let display_name = truncate_name(&habit.name, max_name_width);
 
-    let block = Block::default()
+    let mut block = Block::default()
         .borders(Borders::ALL)
         .border_style(border_style)
         .title(format!(" {} ", display_name));
 
+    // Schedule and today's progress for measured habits, in the top-right corner
+    let mut details = Vec::new();
+    if habit.schedule != Schedule::Daily {
+        details.push(habit.schedule.to_string());
+    }
+    if let Some(progress) = habit.progress_label(today) {
+        details.push(progress);
+    }
+    if !details.is_empty() {
+        block = block.title(
+            Line::styled(format!(" {} ", details.join(" · ")), Style::default().fg(theme.muted))
+                .right_aligned(),
+        );
+    }
+
     let inner_area = block.inner(area);
     frame.render_widget(block, area);
 
-    if show_stats {
+    if app.show_stats {
         let content_layout = Layout::vertical([
             Constraint::Length(1), // Stats row
             Constraint::Min(0),    // Heatmap area
@@ -132,40 +213,504 @@ fn render_habit_card(frame: &mut Frame, habit: &Habit, area: Rect, is_selected:
         .split(inner_area);
 
         // Stats row
-        let current_streak = habit.current_streak();
-        let longest_streak = habit.longest_streak();
-        let completion_pct = habit.completion_percentage();
+        let current_streak = habit.current_streak(app.clock.as_ref(), &app.data.vacations);
+        let longest_streak = habit.longest_streak(app.clock.as_ref(), &app.data.vacations);
+        let completion_pct = habit.completion_percentage(app.clock.as_ref(), &app.data.vacations);
 
-        let streak_text = if current_streak == 1 {
-            "1 day streak".to_string()
+        let streak_text = if habit.is_quit() {
+            format!("{} days clean", current_streak)
         } else {
-            format!("{} day streak", current_streak)
+            format!("{} {} streak", current_streak, habit.streak_unit())
         };
 
         let stats = Paragraph::new(Line::from(vec![
             Span::raw(streak_text),
-            Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
+            Span::styled(" │ ", Style::default().fg(theme.muted)),
             Span::raw(format!("Best: {}", longest_streak)),
-            Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
+            Span::styled(" │ ", Style::default().fg(theme.muted)),
             Span::raw(format!("{}%", completion_pct)),
         ]))
         .centered();
         frame.render_widget(stats, content_layout[0]);
 
         // Heatmap grid (with day labels when stats are shown)
-        let heatmap_lines = build_heatmap(habit, content_layout[1].width, true);
+        let heatmap_lines = build_heatmap(app, habit, content_layout[1].width, true, cursor);
         let heatmap = Paragraph::new(heatmap_lines);
         frame.render_widget(heatmap, content_layout[1]);
     } else {
         // Just render the heatmap (no day labels)
-        let heatmap_lines = build_heatmap(habit, inner_area.width, false);
+        let heatmap_lines = build_heatmap(app, habit, inner_area.width, false, cursor);
         let heatmap = Paragraph::new(heatmap_lines);
         frame.render_widget(heatmap, inner_area);
     }
 }
 
-fn build_heatmap(habit: &Habit, width: u16, show_day_labels: bool) -> Vec<Line<'static>> {
-    let today = chrono::Local::now().date_naive();
+const DETAIL_STATS_WIDTH: u16 = 30;
+/// Rows for the stats, which run longer than the grid for a habit to quit
+const DETAIL_STATS_HEIGHT: u16 = 12;
+
+/// Full-year view of the selected habit: one column per week of `app.detail_year`
+/// with month labels on top, the habit's stats alongside and its notes below
+fn render_detail_view(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let Some(habit) = app.selected_habit() else {
+        return;
+    };
+    let year = app.detail_year;
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(format!(" {} ", habit.name))
+        .title(Line::styled(format!(" {} ", year), Style::default().fg(theme.accent)).right_aligned());
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let (Some(first_day), Some(last_day)) = (
+        NaiveDate::from_ymd_opt(year, 1, 1),
+        NaiveDate::from_ymd_opt(year, 12, 31),
+    ) else {
+        return;
+    };
+    let start_date = week_end(first_day, app.clock.week_start()) - Duration::days(6);
+    let num_weeks = ((week_end(last_day, app.clock.week_start()) - start_date).num_days() as usize + 1) / 7;
+
+    // Day labels plus one cell per week, spaced out like the cards when there's room
+    let compact_width = (2 + num_weeks) as u16;
+    let spaced_width = (2 + num_weeks * 2 - 1) as u16;
+
+    let padded = inner.inner(ratatui::layout::Margin::new(1, 1));
+    let (grid_area, stats_area, notes_area) = if padded.width >= compact_width + DETAIL_STATS_WIDTH + 2 {
+        let rows = Layout::vertical([Constraint::Length(DETAIL_STATS_HEIGHT + 1), Constraint::Min(0)])
+            .split(padded);
+        let columns = Layout::horizontal([Constraint::Min(0), Constraint::Length(DETAIL_STATS_WIDTH)])
+            .spacing(2)
+            .split(rows[0]);
+        (columns[0], columns[1], rows[1])
+    } else {
+        let rows = Layout::vertical([
+            Constraint::Length(9),
+            Constraint::Length(DETAIL_STATS_HEIGHT + 1),
+            Constraint::Min(0),
+        ])
+        .split(padded);
+        (rows[0], rows[1], rows[2])
+    };
+    let selected_note = app.detail_note.and_then(|index| habit.notes.iter().nth(index));
+    let step = if grid_area.width >= spaced_width { 2 } else { 1 };
+
+    // Month names over the first week that contains the 1st of the month
+    let mut month_row = vec![' '; 2 + num_weeks * step];
+    let mut next_free = 0;
+    for week in 0..num_weeks {
+        let week_start = start_date + Duration::days(week as i64 * 7);
+        let first_of_month = (0..7)
+            .map(|day| week_start + Duration::days(day))
+            .find(|d| d.day() == 1 && d.year() == year);
+        let position = 2 + week * step;
+        if let Some(date) = first_of_month
+            && position >= next_free
+        {
+            let name = date.format("%b").to_string();
+            for (i, c) in name.chars().enumerate() {
+                if let Some(slot) = month_row.get_mut(position + i) {
+                    *slot = c;
+                }
+            }
+            next_free = position + name.len() + 1;
+        }
+    }
+
+    let mut lines = vec![Line::styled(
+        month_row.into_iter().collect::<String>(),
+        Style::default().fg(theme.muted),
+    )];
+    for (row, label) in day_labels(app.clock.week_start()).iter().enumerate() {
+        let mut spans = vec![Span::raw(format!("{} ", label))];
+        for week in 0..num_weeks {
+            let date = start_date + Duration::days((week * 7 + row) as i64);
+            if date.year() == year {
+                let mut span = HeatmapCell::for_date(habit, date, app.today, &app.data.vacations).span(theme);
+                if selected_note.is_some_and(|(&note_date, _)| note_date == date) {
+                    span = span.style(Style::default().fg(theme.highlight_text).bg(theme.accent));
+                }
+                spans.push(span);
+            } else {
+                spans.push(Span::raw(" "));
+            }
+            if step == 2 && week < num_weeks - 1 {
+                spans.push(Span::raw(" "));
+            }
+        }
+        lines.push(Line::from(spans));
+    }
+    frame.render_widget(Paragraph::new(lines), grid_area);
+
+    let clock = app.clock.as_ref();
+    let unit = habit.streak_unit();
+    let plural = |n: u32| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    let done_in_year = habit.completions.iter().filter(|d| d.year() == year).count();
+    let current_streak = habit.current_streak(clock, &app.data.vacations);
+    let longest_streak = habit.longest_streak(clock, &app.data.vacations);
+    let completion_pct = habit.completion_percentage(clock, &app.data.vacations);
+
+    let stats = if habit.is_quit() {
+        vec![
+            ("Quitting since", habit.first_tracked_day().format("%Y-%m-%d").to_string()),
+            ("Days clean", plural(current_streak)),
+            ("Best clean run", plural(longest_streak)),
+            ("Clean days", format!("{}%", completion_pct)),
+            ("Slips in year", done_in_year.to_string()),
+            ("Slips in total", habit.completions.len().to_string()),
+        ]
+    } else {
+        let mut stats = vec![("Schedule", habit.schedule.to_string())];
+        if let HabitKind::Measure { .. } = habit.kind {
+            stats.push(("Target", habit.kind.to_string()));
+        }
+        stats.extend([
+            ("Current streak", plural(current_streak)),
+            ("Best streak", plural(longest_streak)),
+            ("Completion", format!("{}%", completion_pct)),
+            ("Done in year", format!("{} days", done_in_year)),
+            ("Done in total", format!("{} days", habit.completions.len())),
+            ("Tracking since", habit.created_at.format("%Y-%m-%d").to_string()),
+        ]);
+        stats
+    };
+    let mut stats: Vec<Line> = stats
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!("{:<16}", label), Style::default().fg(theme.muted)),
+                Span::raw(value),
+            ])
+        })
+        .collect();
+
+    // Slips in each month of the year, three months to a line
+    if habit.is_quit() {
+        let relapses = habit.relapses_by_month();
+        stats.push(Line::from(""));
+        stats.push(Line::styled("Slips by month", Style::default().fg(theme.muted)));
+        for quarter in 0..4 {
+            let mut spans = Vec::new();
+            for month in quarter * 3 + 1..=quarter * 3 + 3 {
+                let count = relapses.get(&(year, month)).copied().unwrap_or(0);
+                let name = NaiveDate::from_ymd_opt(year, month, 1).map_or(String::new(), |d| d.format("%b").to_string());
+                let style = if count > 0 {
+                    Style::default().fg(theme.error)
+                } else {
+                    Style::default()
+                };
+                spans.push(Span::styled(format!("{} ", name), Style::default().fg(theme.muted)));
+                spans.push(Span::styled(format!("{:>3}  ", count), style));
+            }
+            stats.push(Line::from(spans));
+        }
+    }
+    frame.render_widget(Paragraph::new(stats), stats_area);
+
+    render_detail_notes(frame, app, habit, notes_area);
+}
+
+/// The habit's notes, oldest first, scrolled to keep the selected one in view
+fn render_detail_notes(frame: &mut Frame, app: &App, habit: &Habit, area: Rect) {
+    let theme = &app.theme;
+    let mut lines = vec![Line::styled(
+        format!("Notes ({})", habit.notes.len()),
+        Style::default().fg(theme.muted),
+    )];
+    if habit.notes.is_empty() {
+        lines.push(Line::styled(
+            "Press n on a day to write one",
+            Style::default().fg(theme.muted),
+        ));
+    }
+
+    let visible = (area.height as usize).saturating_sub(1);
+    let skip = app
+        .detail_note
+        .map_or(0, |index| (index + 1).saturating_sub(visible));
+    for (index, (date, note)) in habit.notes.iter().enumerate().skip(skip).take(visible) {
+        let style = if app.detail_note == Some(index) {
+            Style::default().fg(theme.highlight_text).bg(theme.accent)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}  ", date.format("%a %Y-%m-%d")), Style::default().fg(theme.muted)),
+            Span::styled(note.clone(), style),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+/// Archived habits with when they were archived and how much history they keep
+fn render_archive_view(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(" Archived habits ");
+    let inner = block.inner(area).inner(ratatui::layout::Margin::new(1, 1));
+    frame.render_widget(block, area);
+
+    let archived = app.archived_habits();
+    if archived.is_empty() {
+        let empty = Paragraph::new(Line::styled(
+            "No archived habits. Press D on a habit to archive it.",
+            Style::default().fg(theme.muted),
+        ));
+        frame.render_widget(empty, inner);
+        return;
+    }
+
+    let name_width = (inner.width as usize).saturating_sub(40).clamp(10, 40);
+    let mut lines = vec![Line::styled(
+        format!("{:<name_width$}  {:<10}  {:>7}  {:>5}", "Habit", "Archived", "Best", "Total"),
+        Style::default().fg(theme.muted),
+    )];
+
+    // Keep the selection in view when the list is longer than the screen
+    let visible = (inner.height as usize).saturating_sub(1);
+    let skip = (app.archive_index + 1).saturating_sub(visible);
+    for (position, &index) in archived.iter().enumerate().skip(skip).take(visible) {
+        let habit = &app.data.habits[index];
+        let archived_on = habit.archived_on.map_or(String::new(), |d| d.to_string());
+        let best = habit.longest_streak(app.clock.as_ref(), &app.data.vacations);
+        let row = format!(
+            "{:<name_width$}  {:<10}  {:>7}  {:>5}",
+            truncate_name(&habit.name, name_width),
+            archived_on,
+            best,
+            habit.completions.len(),
+        );
+        let style = if position == app.archive_index {
+            Style::default().fg(theme.highlight_text).bg(theme.accent)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::styled(row, style));
+    }
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Month calendar around the selected day, marking each day for the selected habit
+/// or counting how many of all habits were done
+fn render_calendar_view(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let selected = app.entry_date();
+    let Some(first_day) = selected.with_day(1) else {
+        return;
+    };
+    let habit = app.selected_habit().filter(|_| !app.calendar_all);
+    let title = habit.map_or("All habits".to_string(), |h| h.name.clone());
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent))
+        .title(format!(" {} ", title))
+        .title(
+            Line::styled(format!(" {} ", first_day.format("%B %Y")), Style::default().fg(theme.accent))
+                .right_aligned(),
+        );
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let cell_width = (inner.width.saturating_sub(2) / 7).clamp(4, 12) as usize;
+    let start_date = week_end(first_day, app.clock.week_start()) - Duration::days(6);
+
+    let mut header: Vec<Span> = Vec::new();
+    for day in 0..7 {
+        let name = (start_date + Duration::days(day)).format("%a").to_string();
+        header.push(Span::styled(
+            format!("{:^width$}", name, width = cell_width),
+            Style::default().fg(theme.muted),
+        ));
+    }
+    let mut lines = vec![Line::from(""), Line::from(header), Line::from("")];
+
+    let mut month_total = 0;
+    let mut week_start = start_date;
+    while week_start.month() == first_day.month() || week_start < first_day {
+        let mut numbers: Vec<Span> = Vec::new();
+        let mut markers: Vec<Span> = Vec::new();
+
+        for day in 0..7 {
+            let date = week_start + Duration::days(day);
+            if date.month() != first_day.month() {
+                numbers.push(Span::raw(" ".repeat(cell_width)));
+                markers.push(Span::raw(" ".repeat(cell_width)));
+                continue;
+            }
+
+            let number_style = if date == selected {
+                Style::default().fg(theme.highlight_text).bg(theme.accent)
+            } else if date == app.today {
+                Style::default().fg(theme.accent)
+            } else {
+                Style::default()
+            };
+            let number = format!("{:>2}", date.day());
+            let padding = cell_width.saturating_sub(2);
+            numbers.push(Span::raw(" ".repeat(padding / 2)));
+            numbers.push(Span::styled(number, number_style));
+            numbers.push(Span::raw(" ".repeat(padding - padding / 2)));
+
+            let marker = match habit {
+                Some(habit) => {
+                    let cell = HeatmapCell::for_date(habit, date, app.today, &app.data.vacations);
+                    let counted = if habit.is_quit() {
+                        matches!(cell, HeatmapCell::Slipped)
+                    } else {
+                        habit.progress_on(date) >= 1.0
+                    };
+                    if counted {
+                        month_total += 1;
+                    }
+                    // Under the day number's last digit
+                    let left = padding / 2 + 1;
+                    let span = cell.span(theme);
+                    Span::styled(
+                        format!("{}{}{}", " ".repeat(left), span.content, " ".repeat(cell_width - left - 1)),
+                        span.style,
+                    )
+                }
+                None if date > app.today => Span::raw(" ".repeat(cell_width)),
+                None => {
+                    // Only habits due that day count against the total: not ones skipped or off
+                    // schedule, nor weekly/monthly quotas, which no single day is missed for
+                    let tracked = app.data.habits.iter().filter(|h| {
+                        let due = h.is_scheduled(date)
+                            && (h.is_quit() || h.schedule.is_day_based())
+                            && !h.is_excused(date, &app.data.vacations);
+                        !h.is_archived() && h.first_tracked_day() <= date && (h.progress_on(date) >= 1.0 || due)
+                    });
+                    let total = tracked.clone().count();
+                    let done = tracked.filter(|h| h.progress_on(date) >= 1.0).count();
+                    month_total += done;
+                    let style = if total > 0 && done == total {
+                        Style::default().fg(theme.done)
+                    } else if done == 0 {
+                        Style::default().fg(theme.muted)
+                    } else {
+                        Style::default()
+                    };
+                    Span::styled(
+                        format!("{:^width$}", format!("{}/{}", done, total), width = cell_width),
+                        style,
+                    )
+                }
+            };
+            markers.push(marker);
+        }
+
+        lines.push(Line::from(numbers));
+        lines.push(Line::from(markers));
+        lines.push(Line::from(""));
+        week_start += Duration::days(7);
+    }
+
+    let summary = match habit {
+        Some(habit) if habit.is_quit() => {
+            format!("{} slips in {}", month_total, first_day.format("%B"))
+        }
+        Some(_) => format!("{} days done in {}", month_total, first_day.format("%B")),
+        None => format!("{} completions in {}", month_total, first_day.format("%B")),
+    };
+    lines.push(Line::styled(summary, Style::default().fg(theme.muted)));
+    if let Some(note) = habit.and_then(|h| h.note_on(selected)) {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}  ", selected.format("%b %-d")), Style::default().fg(theme.muted)),
+            Span::raw(note.to_string()),
+        ]));
+    }
+
+    let width = (cell_width * 7) as u16;
+    let calendar_area = Rect {
+        x: inner.x + inner.width.saturating_sub(width) / 2,
+        width: width.min(inner.width),
+        ..inner
+    };
+    frame.render_widget(Paragraph::new(lines), calendar_area);
+}
+
+/// State of a single day in the heatmap grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeatmapCell {
+    Done,
+    /// Part of a measured habit's target was reached (level 1-3, in thirds)
+    Partial(u8),
+    Missed,
+    /// A slip in a habit to quit
+    Slipped,
+    /// Not done, but skipped or on vacation, so it isn't counted as a miss
+    Skipped,
+    /// Not a due day for the habit's schedule, so it isn't counted as a miss
+    Unscheduled,
+    Future,
+}
+
+impl HeatmapCell {
+    fn for_date(habit: &Habit, date: NaiveDate, today: NaiveDate, away: &[Vacation]) -> Self {
+        if date > today {
+            return HeatmapCell::Future;
+        }
+        // Colors are inverted for a habit to quit: clean days are the done ones
+        if habit.is_quit() {
+            return if habit.completions.contains(&date) {
+                HeatmapCell::Slipped
+            } else if date < habit.first_tracked_day() {
+                HeatmapCell::Unscheduled
+            } else {
+                HeatmapCell::Done
+            };
+        }
+        let progress = habit.progress_on(date);
+        if progress >= 1.0 {
+            HeatmapCell::Done
+        } else if progress > 0.0 {
+            HeatmapCell::Partial(((progress * 3.0).ceil() as u8).clamp(1, 3))
+        } else if habit.is_excused(date, away) {
+            HeatmapCell::Skipped
+        } else if habit.is_scheduled(date) {
+            HeatmapCell::Missed
+        } else {
+            HeatmapCell::Unscheduled
+        }
+    }
+
+    fn span(self, theme: &Theme) -> Span<'static> {
+        match self {
+            HeatmapCell::Done => Span::styled("■", Style::default().fg(theme.done)),
+            HeatmapCell::Partial(level) => {
+                let glyph = match level {
+                    1 => "░",
+                    2 => "▒",
+                    _ => "▓",
+                };
+                Span::styled(glyph, Style::default().fg(theme.done))
+            }
+            HeatmapCell::Missed => Span::styled("□", Style::default().fg(theme.muted)),
+            HeatmapCell::Slipped => Span::styled("■", Style::default().fg(theme.error)),
+            HeatmapCell::Skipped => Span::styled("-", Style::default().fg(theme.skipped)),
+            HeatmapCell::Unscheduled => Span::styled("·", Style::default().fg(theme.muted)),
+            HeatmapCell::Future => Span::raw(" "),
+        }
+    }
+}
+
+fn build_heatmap(
+    app: &App,
+    habit: &Habit,
+    width: u16,
+    show_day_labels: bool,
+    cursor: Option<NaiveDate>,
+) -> Vec<Line<'static>> {
+    let theme = &app.theme;
+    let today = app.today;
 
     // Calculate how many week columns can fit in the available width
     // With day labels: "S " (2 chars) + n cells (1 char each) + (n-1) spaces = 2 + 2n - 1 = 2n + 1
@@ -188,30 +733,32 @@ fn build_heatmap(habit: &Habit, width: u16, show_day_labels: bool) -> Vec<Line<'
         ((width.saturating_sub(grid_width)) / 2) as usize
     };
 
-    // Find the Saturday at or after today to end the grid
-    let end_date = find_next_saturday(today);
+    // Find the last day of today's week to end the grid
+    let mut end_date = week_end(today, app.clock.week_start());
 
-    // Calculate start date: Sunday of the first week
-    // end_date is Saturday, so Sunday of that week is end_date - 6
+    // Calculate start date: first day of the first week
+    // end_date is the last day of its week, so the first day of that week is end_date - 6
     // Then go back (num_weeks - 1) full weeks
-    let start_date = end_date - Duration::days(6 + (num_weeks as i64 - 1) * 7);
+    let mut start_date = end_date - Duration::days(6 + (num_weeks as i64 - 1) * 7);
 
-    // Build grid: 7 rows (Sun=0 through Sat=6), num_weeks columns
-    let mut grid: Vec<Vec<Option<bool>>> = vec![vec![None; num_weeks]; 7];
+    // Scroll back so a cursor on an older date stays visible, with its week in the first column
+    if let Some(cursor) = cursor.filter(|&c| c < start_date) {
+        end_date = week_end(cursor, app.clock.week_start()) + Duration::days((num_weeks as i64 - 1) * 7);
+        start_date = end_date - Duration::days(6 + (num_weeks as i64 - 1) * 7);
+    }
 
-    let mut current_date = start_date;
-    for week in 0..num_weeks {
-        for day in 0..7 {
-            if current_date <= today {
-                let is_completed = habit.completions.contains(&current_date);
-                grid[day][week] = Some(is_completed);
-            }
-            current_date += Duration::days(1);
+    // Build grid: 7 rows (one per weekday, from the week start), num_weeks columns
+    let mut grid: Vec<Vec<HeatmapCell>> = vec![vec![HeatmapCell::Future; num_weeks]; 7];
+
+    for (day, row) in grid.iter_mut().enumerate() {
+        for (week, cell) in row.iter_mut().enumerate() {
+            let date = start_date + Duration::days((week * 7 + day) as i64);
+            *cell = HeatmapCell::for_date(habit, date, today, &app.data.vacations);
         }
     }
 
     // Build display lines
-    let day_labels = ["S", "M", "T", "W", "T", "F", "S"];
+    let day_labels = day_labels(app.clock.week_start());
     let mut lines: Vec<Line<'static>> = Vec::new();
 
     for (row_idx, label) in day_labels.iter().enumerate() {
@@ -228,11 +775,11 @@ fn build_heatmap(habit: &Habit, width: u16, show_day_labels: bool) -> Vec<Line<'
         }
 
         for (week_idx, cell) in grid[row_idx].iter().enumerate() {
-            let span = match cell {
-                Some(true) => Span::styled("■", Style::default().fg(Color::Green)),
-                Some(false) => Span::styled("□", Style::default().fg(Color::DarkGray)),
-                None => Span::raw(" "), // Future date
-            };
+            let date = start_date + Duration::days((week_idx * 7 + row_idx) as i64);
+            let mut span = cell.span(theme);
+            if cursor == Some(date) {
+                span = span.style(Style::default().fg(theme.highlight_text).bg(theme.accent));
+            }
             spans.push(span);
 
             // Add space between weeks (every 7 days)
@@ -247,68 +794,184 @@ fn build_heatmap(habit: &Habit, width: u16, show_day_labels: bool) -> Vec<Line<'
     lines
 }
 
-fn find_next_saturday(date: NaiveDate) -> NaiveDate {
-    let weekday = date.weekday();
-    let days_until_saturday = match weekday {
-        Weekday::Sun => 6,
-        Weekday::Mon => 5,
-        Weekday::Tue => 4,
-        Weekday::Wed => 3,
-        Weekday::Thu => 2,
-        Weekday::Fri => 1,
-        Weekday::Sat => 0,
-    };
-    date + Duration::days(days_until_saturday)
+/// Last day of the week containing `date`, for weeks starting on `week_start`
+fn week_end(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    start_of_week(date, week_start) + Duration::days(6)
 }
 
-fn render_controls_bar() -> Paragraph<'static> {
-    let separator = Span::styled(" │ ", Style::default().fg(Color::DarkGray));
+/// One-letter weekday labels for the heatmap rows, starting at `week_start`
+fn day_labels(week_start: Weekday) -> [&'static str; 7] {
+    let mut labels = ["S", "M", "T", "W", "T", "F", "S"];
+    labels.rotate_left(week_start.num_days_from_sunday() as usize);
+    labels
+}
+
+fn render_controls_bar(theme: &Theme) -> Paragraph<'static> {
+    let separator = Span::styled(" │ ", Style::default().fg(theme.muted));
 
     let controls = Line::from(vec![
-        Span::styled("h/j/k/l", Style::default().fg(Color::Yellow)),
+        Span::styled("h/j/k/l", Style::default().fg(theme.accent)),
         Span::raw(": navigate"),
         separator.clone(),
-        Span::styled("Enter", Style::default().fg(Color::Yellow)),
-        Span::raw(": toggle today"),
+        Span::styled("Enter", Style::default().fg(theme.accent)),
+        Span::raw(": toggle"),
         separator.clone(),
-        Span::styled("a", Style::default().fg(Color::Yellow)),
+        Span::styled("a", Style::default().fg(theme.accent)),
         Span::raw(": add"),
         separator.clone(),
-        Span::styled("r", Style::default().fg(Color::Yellow)),
-        Span::raw(": rename"),
-        separator.clone(),
-        Span::styled("D", Style::default().fg(Color::Yellow)),
-        Span::raw(": delete"),
+        Span::styled("u", Style::default().fg(theme.accent)),
+        Span::raw(": undo"),
         separator.clone(),
-        Span::styled("s", Style::default().fg(Color::Yellow)),
-        Span::raw(": stats"),
+        Span::styled("?", Style::default().fg(theme.accent)),
+        Span::raw(": all keys"),
         separator,
-        Span::styled("q", Style::default().fg(Color::Yellow)),
+        Span::styled("q", Style::default().fg(theme.accent)),
         Span::raw(": quit"),
     ]);
 
     Paragraph::new(controls).centered()
 }
 
-fn render_empty_state() -> Paragraph<'static> {
-    let lines = vec![
+fn render_status_bar(status: &str, theme: &Theme) -> Paragraph<'static> {
+    Paragraph::new(Span::styled(status.to_string(), Style::default().fg(theme.accent))).centered()
+}
+
+fn render_detail_bar(app: &App) -> Paragraph<'static> {
+    let theme = &app.theme;
+    let separator = Span::styled(" │ ", Style::default().fg(theme.muted));
+
+    let controls = Line::from(vec![
+        Span::styled(app.detail_year.to_string(), Style::default().fg(theme.accent)),
+        separator.clone(),
+        Span::styled("h/l", Style::default().fg(theme.accent)),
+        Span::raw(": year"),
+        separator.clone(),
+        Span::styled("j/k", Style::default().fg(theme.accent)),
+        Span::raw(": notes"),
+        separator,
+        Span::styled("Esc", Style::default().fg(theme.accent)),
+        Span::raw(": back"),
+    ]);
+
+    Paragraph::new(controls).centered()
+}
+
+fn render_archive_bar(theme: &Theme) -> Paragraph<'static> {
+    let separator = Span::styled(" │ ", Style::default().fg(theme.muted));
+
+    let controls = Line::from(vec![
+        Span::styled("j/k", Style::default().fg(theme.accent)),
+        Span::raw(": navigate"),
+        separator.clone(),
+        Span::styled("Enter", Style::default().fg(theme.accent)),
+        Span::raw(": restore"),
+        separator.clone(),
+        Span::styled("D", Style::default().fg(theme.accent)),
+        Span::raw(": delete forever"),
+        separator.clone(),
+        Span::styled("u", Style::default().fg(theme.accent)),
+        Span::raw(": undo"),
+        separator,
+        Span::styled("Esc", Style::default().fg(theme.accent)),
+        Span::raw(": back"),
+    ]);
+
+    Paragraph::new(controls).centered()
+}
+
+fn render_calendar_bar(app: &App) -> Paragraph<'static> {
+    let theme = &app.theme;
+    let separator = Span::styled(" │ ", Style::default().fg(theme.muted));
+    let date = app.entry_date();
+    let scope = if app.calendar_all { ": one habit" } else { ": all habits" };
+
+    let controls = Line::from(vec![
+        Span::styled(date.format("%a %Y-%m-%d").to_string(), Style::default().fg(theme.accent)),
+        separator.clone(),
+        Span::styled("h/j/k/l", Style::default().fg(theme.accent)),
+        Span::raw(": day"),
+        separator.clone(),
+        Span::styled("H/L", Style::default().fg(theme.accent)),
+        Span::raw(": month"),
+        separator.clone(),
+        Span::styled("Tab", Style::default().fg(theme.accent)),
+        Span::raw(scope),
+        separator.clone(),
+        Span::styled("Enter", Style::default().fg(theme.accent)),
+        Span::raw(": toggle day"),
+        separator.clone(),
+        Span::styled("x", Style::default().fg(theme.accent)),
+        Span::raw(": skip"),
+        separator.clone(),
+        Span::styled("n", Style::default().fg(theme.accent)),
+        Span::raw(": note"),
+        separator,
+        Span::styled("Esc", Style::default().fg(theme.accent)),
+        Span::raw(": back"),
+    ]);
+
+    Paragraph::new(controls).centered()
+}
+
+fn render_cursor_bar(app: &App) -> Paragraph<'static> {
+    let theme = &app.theme;
+    let separator = Span::styled(" │ ", Style::default().fg(theme.muted));
+    let date = app.entry_date();
+
+    let controls = Line::from(vec![
+        Span::styled(date.format("%a %Y-%m-%d").to_string(), Style::default().fg(theme.accent)),
+        separator.clone(),
+        Span::styled("h/l", Style::default().fg(theme.accent)),
+        Span::raw(": week"),
+        separator.clone(),
+        Span::styled("j/k", Style::default().fg(theme.accent)),
+        Span::raw(": day"),
+        separator.clone(),
+        Span::styled("Enter", Style::default().fg(theme.accent)),
+        Span::raw(": toggle day"),
+        separator.clone(),
+        Span::styled("x", Style::default().fg(theme.accent)),
+        Span::raw(": skip"),
+        separator.clone(),
+        Span::styled("n", Style::default().fg(theme.accent)),
+        Span::raw(": note"),
+        separator,
+        Span::styled("Esc", Style::default().fg(theme.accent)),
+        Span::raw(": done"),
+    ]);
+
+    Paragraph::new(controls).centered()
+}
+
+fn render_empty_state(theme: &Theme, archived: usize) -> Paragraph<'static> {
+    let mut lines = vec![
         Line::from(""),
         Line::from(Span::styled(
-            "No habits yet",
-            Style::default().fg(Color::DarkGray),
+            if archived > 0 { "No active habits" } else { "No habits yet" },
+            Style::default().fg(theme.muted),
         )),
         Line::from(""),
         Line::from(vec![
             Span::raw("Press "),
-            Span::styled("a", Style::default().fg(Color::Yellow)),
+            Span::styled("a", Style::default().fg(theme.accent)),
             Span::raw(" to add your first habit"),
         ]),
     ];
+    if archived > 0 {
+        lines[3] = Line::from(vec![
+            Span::raw("Press "),
+            Span::styled("a", Style::default().fg(theme.accent)),
+            Span::raw(" to add a habit, or "),
+            Span::styled("A", Style::default().fg(theme.accent)),
+            Span::raw(format!(" to see {} archived", archived)),
+        ]);
+    }
 
     Paragraph::new(lines).centered()
 }
 
 fn render_add_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let popup_width = 32;
     let popup_height = 6;
 
@@ -320,7 +983,7 @@ fn render_add_popup(frame: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
         .title(" Add New Habit ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Yellow));
+        .border_style(Style::default().fg(theme.accent));
 
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
@@ -338,22 +1001,23 @@ fn render_add_popup(frame: &mut Frame, app: &App, area: Rect) {
         Span::raw("  Name: "),
         Span::styled(
             format!("{}_", app.input_buffer),
-            Style::default().fg(Color::White),
+            Style::default().fg(theme.input),
         ),
     ]);
     frame.render_widget(Paragraph::new(input_line), layout[1]);
 
     // Help line
     let help = Line::from(vec![
-        Span::styled("  Enter", Style::default().fg(Color::Yellow)),
+        Span::styled("  Enter", Style::default().fg(theme.accent)),
         Span::raw(": confirm  "),
-        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::styled("Esc", Style::default().fg(theme.accent)),
         Span::raw(": cancel"),
     ]);
     frame.render_widget(Paragraph::new(help), layout[3]);
 }
 
 fn render_rename_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let popup_width = 32;
     let popup_height = 6;
 
@@ -365,7 +1029,7 @@ fn render_rename_popup(frame: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
         .title(" Rename Habit ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Yellow));
+        .border_style(Style::default().fg(theme.accent));
 
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
@@ -383,30 +1047,143 @@ fn render_rename_popup(frame: &mut Frame, app: &App, area: Rect) {
         Span::raw("  Name: "),
         Span::styled(
             format!("{}_", app.input_buffer),
-            Style::default().fg(Color::White),
+            Style::default().fg(theme.input),
         ),
     ]);
     frame.render_widget(Paragraph::new(input_line), layout[1]);
 
     // Help line
     let help = Line::from(vec![
-        Span::styled("  Enter", Style::default().fg(Color::Yellow)),
+        Span::styled("  Enter", Style::default().fg(theme.accent)),
         Span::raw(": confirm  "),
-        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::styled("Esc", Style::default().fg(theme.accent)),
         Span::raw(": cancel"),
     ]);
     frame.render_widget(Paragraph::new(help), layout[3]);
 }
 
+fn render_schedule_popup(frame: &mut Frame, app: &App, area: Rect) {
+    render_hinted_input_popup(
+        frame,
+        app,
+        area,
+        " Set Frequency ",
+        "Every",
+        "daily, mon,fri, 3x/week, every 2 days".to_string(),
+    );
+}
+
+fn render_target_popup(frame: &mut Frame, app: &App, area: Rect) {
+    render_hinted_input_popup(
+        frame,
+        app,
+        area,
+        " Set Daily Target ",
+        "Target",
+        "e.g. 8 glasses, quit, empty for yes/no".to_string(),
+    );
+}
+
+fn render_note_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let date = app.entry_date();
+    let title = if date == app.today {
+        " Note for Today ".to_string()
+    } else {
+        format!(" Note for {} ", date.format("%a %b %-d"))
+    };
+    render_hinted_input_popup(frame, app, area, &title, "Note", "empty to remove".to_string());
+}
+
+fn render_amount_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let hint = match app.selected_habit().map(|h| &h.kind) {
+        Some(HabitKind::Measure { target, unit }) => {
+            format!("target {} {}, +n to add", format_amount(*target), unit)
+        }
+        _ => String::new(),
+    };
+    let date = app.entry_date();
+    let title = if date == app.today {
+        " Log Today ".to_string()
+    } else {
+        format!(" Log {} ", date.format("%a %b %-d"))
+    };
+    render_hinted_input_popup(frame, app, area, &title, "Amount", hint);
+}
+
+/// Input popup with a hint line under the input, replaced by the parse error if the last attempt failed
+fn render_hinted_input_popup(
+    frame: &mut Frame,
+    app: &App,
+    area: Rect,
+    title: &str,
+    label: &str,
+    hint: String,
+) {
+    let theme = &app.theme;
+    let prefix = format!("  {}: ", label);
+    let typed = app.input_buffer.chars().count();
+    // Grow with the input up to the screen width, then keep its end in view
+    let popup_width = ((prefix.len() + typed + 4) as u16).clamp(40, area.width.max(40));
+    let popup_height = 7;
+    let room = (popup_width as usize).saturating_sub(prefix.len() + 3);
+    let visible: String = app.input_buffer.chars().skip(typed.saturating_sub(room)).collect();
+
+    let popup_area = centered_rect(popup_width, popup_height, area);
+
+    // Clear the area behind the popup
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(title.to_string())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let layout = Layout::vertical([
+        Constraint::Length(1), // empty line
+        Constraint::Length(1), // input line
+        Constraint::Length(1), // hint or error line
+        Constraint::Length(1), // empty line
+        Constraint::Length(1), // help line
+    ])
+    .split(inner);
+
+    // Input line
+    let input_line = Line::from(vec![
+        Span::raw(prefix),
+        Span::styled(
+            format!("{}_", visible),
+            Style::default().fg(theme.input),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(input_line), layout[1]);
+
+    let hint = match &app.input_error {
+        Some(error) => Line::styled(format!("  {}", error), Style::default().fg(theme.error)),
+        None => Line::styled(format!("  {}", hint), Style::default().fg(theme.muted)),
+    };
+    frame.render_widget(Paragraph::new(hint), layout[2]);
+
+    // Help line
+    let help = Line::from(vec![
+        Span::styled("  Enter", Style::default().fg(theme.accent)),
+        Span::raw(": confirm  "),
+        Span::styled("Esc", Style::default().fg(theme.accent)),
+        Span::raw(": cancel"),
+    ]);
+    frame.render_widget(Paragraph::new(help), layout[4]);
+}
+
 fn render_delete_popup(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
     let habit_name = app
-        .data
-        .habits
-        .get(app.selected_index)
-        .map(|h| truncate_name(&h.name, 17))
+        .selected_archived()
+        .map(|h| truncate_name(&h.name, 20))
         .unwrap_or_default();
 
-    let popup_width = 32;
+    let popup_width = 44;
     let popup_height = 6;
 
     let popup_area = centered_rect(popup_width, popup_height, area);
@@ -415,9 +1192,9 @@ fn render_delete_popup(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Clear, popup_area);
 
     let block = Block::default()
-        .title(" Delete Habit ")
+        .title(" Delete Forever ")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Yellow));
+        .border_style(Style::default().fg(theme.accent));
 
     let inner = block.inner(popup_area);
     frame.render_widget(block, popup_area);
@@ -431,19 +1208,76 @@ fn render_delete_popup(frame: &mut Frame, app: &App, area: Rect) {
     .split(inner);
 
     // Prompt line
-    let prompt = Line::from(format!("  Delete \"{}\"?", habit_name));
+    let prompt = Line::from(format!("  Delete \"{}\" for good?", habit_name));
     frame.render_widget(Paragraph::new(prompt), layout[1]);
 
     // Help line
     let help = Line::from(vec![
-        Span::styled("  y", Style::default().fg(Color::Yellow)),
+        Span::styled("  y", Style::default().fg(theme.accent)),
         Span::raw(": yes  "),
-        Span::styled("n", Style::default().fg(Color::Yellow)),
+        Span::styled("n", Style::default().fg(theme.accent)),
         Span::raw(": no"),
     ]);
     frame.render_widget(Paragraph::new(help), layout[3]);
 }
 
+/// Keys of the main screen, in the order the help popup lists them (down, then across)
+const HELP_KEYS: [(&str, &str); 20] = [
+    ("h/j/k/l", "navigate"),
+    ("g/G", "first/last habit"),
+    ("Enter", "toggle today"),
+    ("e", "edit past days"),
+    ("x", "skip today"),
+    ("n", "note for today"),
+    ("v", "year view"),
+    ("c", "calendar"),
+    ("s", "stats"),
+    ("q", "quit"),
+    ("a", "add"),
+    ("r", "rename"),
+    ("t", "target"),
+    ("f", "frequency"),
+    ("</>", "move"),
+    ("D", "archive"),
+    ("A", "archived habits"),
+    ("u", "undo"),
+    ("Ctrl-r", "redo"),
+    ("?", "close this help"),
+];
+
+fn render_help_popup(frame: &mut Frame, theme: &Theme, area: Rect) {
+    let rows = HELP_KEYS.len().div_ceil(2);
+    let popup_area = centered_rect(56, rows as u16 + 4, area);
+
+    // Clear the area behind the popup
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title(" Keys ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent));
+
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let entry = |(key, action): (&'static str, &'static str)| {
+        [
+            Span::styled(format!("  {:>7}", key), Style::default().fg(theme.accent)),
+            Span::raw(format!("  {:<16}", action)),
+        ]
+    };
+    let mut lines = vec![Line::from("")];
+    let (left, right) = HELP_KEYS.split_at(rows);
+    for (row, &key) in left.iter().enumerate() {
+        let mut spans = entry(key).to_vec();
+        if let Some(&other) = right.get(row) {
+            spans.extend(entry(other));
+        }
+        lines.push(Line::from(spans));
+    }
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
 fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
     let x = area.x + (area.width.saturating_sub(width)) / 2;
     let y = area.y + (area.height.saturating_sub(height)) / 2;